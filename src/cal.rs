@@ -0,0 +1,238 @@
+use crate::{
+    color::{Colored, CustomColors, DefaultColors},
+    date::Weekday,
+    Date, Rating,
+};
+
+const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+/// A `cal(1)` style month grid with every day coloured by its rating.
+/// Days without a rating are left uncoloured.
+pub struct Calendar<F> {
+    year: u16,
+    month: u8,
+    rating: F,
+}
+
+impl<F> Calendar<F>
+where
+    F: Fn(Date) -> Option<Rating>,
+{
+    pub fn new(year: u16, month: u8, rating: F) -> Self {
+        Self {
+            year,
+            month,
+            rating,
+        }
+    }
+}
+
+struct Day(u8);
+
+impl core::fmt::Display for Day {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{:>2}", self.0)
+    }
+}
+
+impl<F> core::fmt::Display for Calendar<F>
+where
+    F: Fn(Date) -> Option<Rating>,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let name = MONTHS[self.month as usize - 1];
+        let title_len = name.len() + 5;
+        writeln!(
+            f,
+            "{:>1$} {2}",
+            name,
+            (20 - title_len) / 2 + name.len(),
+            self.year
+        )?;
+        writeln!(f, "Su Mo Tu We Th Fr Sa")?;
+
+        let mut date = Date::from_ymd(self.year, self.month, 1);
+        let mut column = sunday_column(date.weekday());
+        if column > 0 {
+            write!(f, "{:1$}", "", column * 3 - 1)?;
+        }
+
+        while date.year() == self.year && date.month_day().0 == self.month {
+            if column > 0 {
+                write!(f, " ")?;
+            }
+            let day = Day(date.month_day().1);
+            match (self.rating)(date) {
+                Some(rating) => write!(f, "{}", day.colored(DefaultColors::map(rating)))?,
+                None => write!(f, "{}", day)?,
+            }
+
+            column += 1;
+            if column == 7 {
+                column = 0;
+                writeln!(f)?;
+            }
+            date = date.successor();
+        }
+        if column != 0 {
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Columns needed for a year starting on a saturday with a 29 February.
+const MAX_WEEKS: usize = 54;
+
+/// A GitHub style heatmap of a whole year, one column per week and one row
+/// per weekday, every day drawn as a block coloured by its rating.
+pub struct Heatmap<F> {
+    year: u16,
+    rating: F,
+}
+
+impl<F> Heatmap<F>
+where
+    F: Fn(Date) -> Option<Rating>,
+{
+    pub fn new(year: u16, rating: F) -> Self {
+        Self { year, rating }
+    }
+}
+
+const fn sunday_column(weekday: Weekday) -> usize {
+    match weekday {
+        Weekday::Sunday => 0,
+        Weekday::Monday => 1,
+        Weekday::Tuesday => 2,
+        Weekday::Wednesday => 3,
+        Weekday::Thursday => 4,
+        Weekday::Friday => 5,
+        Weekday::Saturday => 6,
+    }
+}
+
+impl<F> core::fmt::Display for Heatmap<F>
+where
+    F: Fn(Date) -> Option<Rating>,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        const LABELS: [&str; 7] = ["    ", "Mon ", "    ", "Wed ", "    ", "Fri ", "    "];
+        const BLOCK: &str = "\u{2588}\u{2588}";
+        const NO_RATING: &str = "\u{2591}\u{2591}";
+
+        // `None` outside of the year, `Some(None)` for days without a rating
+        let mut grid = [[None; MAX_WEEKS]; 7];
+        let start = Date::from_ymd(self.year, 1, 1);
+        let offset = sunday_column(start.weekday());
+        let mut header = [b' '; 4 + MAX_WEEKS * 2];
+        let mut header_len = 0;
+
+        let mut date = start;
+        while date.year() == self.year {
+            let cell = offset + date.ordinal() as usize;
+            let (week, row) = (cell / 7, cell % 7);
+            grid[row][week] = Some((self.rating)(date));
+
+            let (month, day) = date.month_day();
+            let column = 4 + week * 2;
+            if day == 1 && column >= header_len {
+                let name = &MONTHS[month as usize - 1].as_bytes()[..3];
+                header[column..column + 3].copy_from_slice(name);
+                header_len = column + 4;
+            }
+            date = date.successor();
+        }
+
+        let header = core::str::from_utf8(&header[..header_len - 1]).unwrap();
+        writeln!(f, "{}", header)?;
+        for (label, row) in LABELS.iter().zip(grid.iter()) {
+            write!(f, "{}", label)?;
+            let len = row.iter().rposition(Option::is_some).map_or(0, |i| i + 1);
+            for cell in &row[..len] {
+                match cell {
+                    Some(Some(rating)) => {
+                        write!(f, "{}", BLOCK.colored(DefaultColors::map(*rating)))?
+                    }
+                    Some(None) => write!(f, "{}", NO_RATING)?,
+                    None => write!(f, "  ")?,
+                }
+            }
+            writeln!(f)?;
+        }
+
+        write!(f, "    ")?;
+        for (i, &rating) in Rating::ALL.iter().enumerate() {
+            if i > 0 {
+                write!(f, "  ")?;
+            }
+            write!(
+                f,
+                "{} {}",
+                BLOCK.colored(DefaultColors::map(rating)),
+                rating
+            )?;
+        }
+        writeln!(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::Plain;
+
+    #[test]
+    fn month_grid() {
+        use core::fmt::Write;
+
+        let mut buf = [0u8; 256];
+        let mut out = Plain(&mut buf, 0);
+        write!(out, "{}", Calendar::new(2021, 2, |_| None)).unwrap();
+        let len = out.1;
+
+        assert_eq!(
+            core::str::from_utf8(&buf[..len]).unwrap(),
+            "   February 2021\n\
+             Su Mo Tu We Th Fr Sa\n\
+             \x20   1  2  3  4  5  6\n\
+             \x207  8  9 10 11 12 13\n\
+             14 15 16 17 18 19 20\n\
+             21 22 23 24 25 26 27\n\
+             28\n"
+        );
+    }
+
+    #[test]
+    fn year_heatmap() {
+        use core::fmt::Write;
+
+        let mut buf = [0u8; 4096];
+        let mut out = Plain(&mut buf, 0);
+        write!(out, "{}", Heatmap::new(2021, |_| None)).unwrap();
+        let len = out.1;
+        let text = core::str::from_utf8(&buf[..len]).unwrap();
+        let mut lines = text.lines();
+
+        assert!(lines.next().unwrap().starts_with("    Jan       Feb"));
+        // 2021 starts on a friday
+        let sunday = lines.next().unwrap();
+        assert!(sunday.starts_with("      \u{2591}\u{2591}"));
+        assert_eq!(text.matches('\u{2591}').count(), 2 * 365);
+        assert_eq!(lines.count(), 7);
+    }
+}
@@ -1,9 +1,70 @@
+use core::sync::atomic::{AtomicU8, Ordering};
+
 use crate::{
-    rating::Rateable,
+    rating::AltRateable,
     set::{Dailylike, Instabs},
-    Fractal, Instability, Rater, Rating, Set,
+    AltPolicy, Fractal, Instability, Rater, Rating, Set,
 };
 
+/// How colours are drawn, picked with `--theme` or the config file.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Theme {
+    Default,
+    /// The bright variants of every colour, for dark terminals.
+    Bright,
+    /// No escape codes at all.
+    Plain,
+    /// Only the eight basic colours, which is all Discord draws.
+    Basic,
+}
+
+impl Theme {
+    pub const KEYWORDS: [&'static str; 4] = ["default", "bright", "plain", "basic"];
+}
+
+impl core::str::FromStr for Theme {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "default" => Theme::Default,
+            "bright" => Theme::Bright,
+            "plain" => Theme::Plain,
+            "basic" => Theme::Basic,
+            _ => return Err(()),
+        })
+    }
+}
+
+impl core::fmt::Display for Theme {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let name = match self {
+            Theme::Default => "default",
+            Theme::Bright => "bright",
+            Theme::Plain => "plain",
+            Theme::Basic => "basic",
+        };
+
+        f.pad(name)
+    }
+}
+
+// colours are picked deep inside Display impls, so the theme is global
+static THEME: AtomicU8 = AtomicU8::new(Theme::Default as u8);
+
+pub fn set_theme(theme: Theme) {
+    THEME.store(theme as u8, Ordering::Relaxed);
+}
+
+fn theme() -> Theme {
+    match THEME.load(Ordering::Relaxed) {
+        1 => Theme::Bright,
+        2 => Theme::Plain,
+        3 => Theme::Basic,
+        _ => Theme::Default,
+    }
+}
+
 pub trait CustomColors {
     fn map(rating: Rating) -> Color;
 }
@@ -66,6 +127,7 @@ where
         ColoredByRater {
             inner: self,
             rater: self.fractal().clone(),
+            alt_policy: AltPolicy::Worst,
         }
     }
 }
@@ -77,6 +139,18 @@ where
 {
     inner: &'a T,
     rater: U,
+    alt_policy: AltPolicy,
+}
+
+impl<T, U> ColoredByRater<'_, T, U>
+where
+    T: ?Sized,
+    U: Rater,
+{
+    pub fn with_alt_policy(mut self, alt_policy: AltPolicy) -> Self {
+        self.alt_policy = alt_policy;
+        self
+    }
 }
 
 pub trait ColorByRater<T>
@@ -84,7 +158,11 @@ where
     T: Rater,
 {
     fn color_by_rater(&self, rater: T) -> ColoredByRater<'_, Self, T> {
-        ColoredByRater { inner: self, rater }
+        ColoredByRater {
+            inner: self,
+            rater,
+            alt_policy: AltPolicy::Worst,
+        }
     }
 }
 
@@ -132,13 +210,17 @@ where
     U: Dailylike,
 {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-        let rating = self.inner.rate(&self.rater);
+        let rating = self.inner.rate_with_alt(&self.rater, self.alt_policy);
         write!(
             f,
             "{}: {}",
             ColorRating::<DefaultColors>::color_by_rating(&self.inner.fractal(), rating),
             self.inner.instabs().color_by_rater(self.rater)
-        )
+        )?;
+        if let Some(alt) = self.inner.alt() {
+            write!(f, " | {}", alt.color_by_rater(self.rater))?;
+        }
+        Ok(())
     }
 }
 
@@ -169,6 +251,7 @@ where
     }
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum Color {
     Normal,
     Bold,
@@ -217,6 +300,41 @@ impl Color {
             BrightWhite => 97,
         }
     }
+
+    const fn brightened(&self) -> Self {
+        use Color::*;
+
+        match self {
+            Black => BrightBlack,
+            Red => BrightRed,
+            Green => BrightGreen,
+            Yellow => BrightYellow,
+            Blue => BrightBlue,
+            Magenta => BrightMagenta,
+            Cyan => BrightCyan,
+            White => BrightWhite,
+            BrightBlack => White,
+            Normal => Bold,
+            _ => *self,
+        }
+    }
+
+    const fn basic(&self) -> Self {
+        use Color::*;
+
+        match self {
+            Faint => Normal,
+            BrightBlack => Black,
+            BrightRed => Red,
+            BrightGreen => Green,
+            BrightYellow => Yellow,
+            BrightBlue => Blue,
+            BrightMagenta => Magenta,
+            BrightCyan => Cyan,
+            BrightWhite => White,
+            _ => *self,
+        }
+    }
 }
 
 pub struct WithColor<'a, T: ?Sized> {
@@ -237,6 +355,12 @@ where
     T: core::fmt::Display,
 {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-        write!(f, "[{}m{}[0m", self.color.code(), self.inner)
+        let code = match theme() {
+            Theme::Default => self.color.code(),
+            Theme::Bright => self.color.brightened().code(),
+            Theme::Basic => self.color.basic().code(),
+            Theme::Plain => return write!(f, "{}", self.inner),
+        };
+        write!(f, "[{}m{}[0m", code, self.inner)
     }
 }
@@ -0,0 +1,372 @@
+use core::fmt::{Formatter, Result};
+
+use crate::{
+    opt::{Command, Expected, Flag, FLAGS},
+    Fractal, Instability,
+};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+impl Shell {
+    pub const KEYWORDS: [&'static str; 3] = ["bash", "zsh", "fish"];
+}
+
+impl core::str::FromStr for Shell {
+    type Err = ();
+
+    fn from_str(s: &str) -> core::result::Result<Self, Self::Err> {
+        Ok(match s {
+            "bash" => Shell::Bash,
+            "zsh" => Shell::Zsh,
+            "fish" => Shell::Fish,
+            _ => return Err(()),
+        })
+    }
+}
+
+impl core::fmt::Display for Shell {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let name = match self {
+            Shell::Bash => "bash",
+            Shell::Zsh => "zsh",
+            Shell::Fish => "fish",
+        };
+
+        f.pad(name)
+    }
+}
+
+/// A completion script, built from `Command::ALL` and `FLAGS` so it knows
+/// everything the parser does.
+pub struct Completions(Shell);
+
+impl Completions {
+    pub const fn new(shell: Shell) -> Self {
+        Self(shell)
+    }
+}
+
+const NONE: [&[&str]; 2] = [&[], &[]];
+
+/// The keywords a value can be completed with.
+fn values(expected: Expected) -> [&'static [&'static str]; 2] {
+    match expected {
+        Expected::OneOf(keywords) => [keywords, &[]],
+        Expected::Scope => [&["cms", "any"], &Fractal::KEYWORDS],
+        _ => NONE,
+    }
+}
+
+/// The keywords the arguments of a command can be completed with.
+fn positionals(command: Command) -> [&'static [&'static str]; 2] {
+    match command {
+        Command::Whereis => [&Fractal::KEYWORDS, &Instability::KEYWORDS],
+        Command::Completions => [&Shell::KEYWORDS, &[]],
+        _ => NONE,
+    }
+}
+
+fn offers(command: Command, flag: &Flag) -> bool {
+    // every command stops at --help and --version
+    matches!(flag.long, "--help" | "--version") || command.accepts(flag.long)
+}
+
+/// Words joined by `sep`, quoting those with spaces when `sep` is one.
+struct List<'a, 'b> {
+    f: &'a mut Formatter<'b>,
+    sep: &'static str,
+    empty: bool,
+}
+
+impl<'a, 'b> List<'a, 'b> {
+    fn new(f: &'a mut Formatter<'b>, sep: &'static str) -> Self {
+        Self {
+            f,
+            sep,
+            empty: true,
+        }
+    }
+
+    fn separate(&mut self) -> Result {
+        if !self.empty {
+            self.f.write_str(self.sep)?;
+        }
+        self.empty = false;
+        Ok(())
+    }
+
+    fn word(&mut self, word: &str) -> Result {
+        self.separate()?;
+        if self.sep == " " && word.contains(' ') {
+            write!(self.f, "'{}'", word)
+        } else {
+            self.f.write_str(word)
+        }
+    }
+
+    fn words(&mut self, lists: [&[&str]; 2]) -> Result {
+        lists
+            .iter()
+            .flat_map(|l| l.iter())
+            .try_for_each(|w| self.word(w))
+    }
+
+    fn flag(&mut self, flag: &Flag) -> Result {
+        if let Some(short) = flag.short {
+            self.separate()?;
+            write!(self.f, "-{}", short)?;
+        }
+        self.word(flag.long)
+    }
+
+    fn commands(&mut self, pred: impl Fn(Command) -> bool) -> Result {
+        for command in Command::ALL.iter().filter(|&&c| pred(c)) {
+            self.separate()?;
+            write!(self.f, "{}", command)?;
+        }
+        Ok(())
+    }
+
+    /// Everything that can follow `command`: its flags and arguments.
+    fn offered_by(&mut self, command: Command) -> Result {
+        for flag in FLAGS.iter().filter(|flag| offers(command, flag)) {
+            self.flag(flag)?;
+        }
+        self.words(positionals(command))
+    }
+}
+
+/// Writes the `case` arms completing flag values in bash and zsh, with
+/// `words` and `files` completing a word list and a path.
+fn value_arms(f: &mut Formatter<'_>, shell: Shell) -> Result {
+    let (words, sep, files) = match shell {
+        Shell::Bash => (
+            "_stab_words $'",
+            "\\n",
+            "COMPREPLY=($(compgen -f -- \"$cur\"))",
+        ),
+        _ => ("compadd -- ", " ", "_files"),
+    };
+
+    for flag in &FLAGS {
+        let expected = match flag.value {
+            Some((_, expected)) => expected,
+            None => continue,
+        };
+        let action = values(expected);
+        if action[0].is_empty() && expected != Expected::File {
+            continue;
+        }
+
+        write!(f, "        ")?;
+        List::new(f, "|").flag(flag)?;
+        write!(f, ")\n            ")?;
+        if expected == Expected::File {
+            write!(f, "{}", files)?;
+        } else {
+            write!(f, "{}", words)?;
+            List::new(f, sep).words(action)?;
+            if shell == Shell::Bash {
+                write!(f, "'")?;
+            }
+        }
+        writeln!(f, "\n            return;;")?;
+    }
+
+    // values that can't be completed still shouldn't complete as flags
+    write!(f, "        ")?;
+    let mut list = List::new(f, "|");
+    for flag in &FLAGS {
+        match flag.value {
+            Some((_, expected)) if values(expected)[0].is_empty() && expected != Expected::File => {
+                list.flag(flag)?
+            }
+            _ => {}
+        }
+    }
+    writeln!(f, ")\n            return;;")
+}
+
+/// Writes the `case` arms completing what follows each command in bash and
+/// zsh, with anything else being the options of `next`.
+fn command_arms(f: &mut Formatter<'_>, shell: Shell) -> Result {
+    let (start, sep, end) = match shell {
+        Shell::Bash => ("_stab_words $'", "\\n", "'"),
+        _ => ("compadd -- ", " ", ""),
+    };
+
+    for &command in Command::ALL.iter().filter(|&&c| c != Command::Next) {
+        writeln!(f, "        {})", command)?;
+        write!(f, "            {}", start)?;
+        List::new(f, sep).offered_by(command)?;
+        writeln!(f, "{};;", end)?;
+    }
+    writeln!(f, "        *)")?;
+    write!(f, "            {}", start)?;
+    List::new(f, sep).offered_by(Command::Next)?;
+    writeln!(f, "{};;", end)
+}
+
+fn bash(f: &mut Formatter<'_>) -> Result {
+    writeln!(
+        f,
+        "\
+_stab_words() {{
+    local IFS=$'\\n' word
+    for word in $1; do
+        [[ $word == \"$cur\"* ]] && COMPREPLY+=(\"$(printf '%q' \"$word\")\")
+    done
+}}
+
+_stab() {{
+    local cur=${{COMP_WORDS[COMP_CWORD]}} prev=${{COMP_WORDS[COMP_CWORD-1]}} cmd=next
+    COMPREPLY=()
+    case $prev in"
+    )?;
+    value_arms(f, Shell::Bash)?;
+    writeln!(f, "    esac\n")?;
+
+    write!(f, "    (( COMP_CWORD == 1 )) && _stab_words $'")?;
+    List::new(f, "\\n").commands(|_| true)?;
+    writeln!(f, "'")?;
+    writeln!(f, "    (( COMP_CWORD > 1 )) && cmd=${{COMP_WORDS[1]}}")?;
+    writeln!(f, "    case $cmd in")?;
+    command_arms(f, Shell::Bash)?;
+    writeln!(f, "    esac\n}}\n\ncomplete -F _stab stab")
+}
+
+fn zsh(f: &mut Formatter<'_>) -> Result {
+    writeln!(
+        f,
+        "\
+#compdef stab
+
+_stab() {{
+    local prev=${{words[CURRENT-1]}} cmd=next
+    case $prev in"
+    )?;
+    value_arms(f, Shell::Zsh)?;
+    writeln!(f, "    esac\n")?;
+
+    write!(f, "    (( CURRENT == 2 )) && compadd -- ")?;
+    List::new(f, " ").commands(|_| true)?;
+    writeln!(f)?;
+    writeln!(f, "    (( CURRENT > 2 )) && cmd=${{words[2]}}")?;
+    writeln!(f, "    case $cmd in")?;
+    command_arms(f, Shell::Zsh)?;
+    writeln!(
+        f,
+        "    esac
+}}
+
+if [ \"$funcstack[1]\" = \"_stab\" ]; then
+    _stab \"$@\"
+else
+    compdef _stab stab
+fi"
+    )
+}
+
+fn fish(f: &mut Formatter<'_>) -> Result {
+    writeln!(f, "complete -c stab -f")?;
+    write!(f, "complete -c stab -n __fish_use_subcommand -a \"")?;
+    List::new(f, " ").commands(|_| true)?;
+    writeln!(f, "\"")?;
+    for &command in &Command::ALL {
+        let words = positionals(command);
+        if !words[0].is_empty() {
+            write!(
+                f,
+                "complete -c stab -n '__fish_seen_subcommand_from {}' -a \"",
+                command
+            )?;
+            List::new(f, " ").words(words)?;
+            writeln!(f, "\"")?;
+        }
+    }
+
+    for flag in &FLAGS {
+        write!(f, "complete -c stab")?;
+        if let Some(short) = flag.short {
+            write!(f, " -s {}", short)?;
+        }
+        write!(f, " -l {}", &flag.long[2..])?;
+
+        match flag.value {
+            Some((_, Expected::File)) => write!(f, " -r -F")?,
+            Some((_, expected)) => {
+                write!(f, " -r")?;
+                let words = values(expected);
+                if !words[0].is_empty() {
+                    write!(f, " -a \"")?;
+                    List::new(f, " ").words(words)?;
+                    write!(f, "\"")?;
+                }
+            }
+            None => {}
+        }
+
+        // without a command the flags are the ones of `next`
+        let offered = |c| offers(c, flag);
+        if Command::ALL.iter().all(|&c| offered(c)) {
+            writeln!(f)?;
+            continue;
+        }
+        if offered(Command::Next) {
+            write!(f, " -n 'not __fish_seen_subcommand_from ")?;
+            List::new(f, " ").commands(|c| !offered(c))?;
+            write!(f, "'")?;
+        } else {
+            write!(f, " -n '__fish_seen_subcommand_from ")?;
+            List::new(f, " ").commands(offered)?;
+            write!(f, "'")?;
+        }
+        writeln!(f)?;
+    }
+    Ok(())
+}
+
+impl core::fmt::Display for Completions {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self.0 {
+            Shell::Bash => bash(f),
+            Shell::Zsh => zsh(f),
+            Shell::Fish => fish(f),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::Plain;
+    use crate::{Boss, Rating};
+    use core::fmt::{Display, Write};
+
+    fn render(value: impl Display, buf: &mut [u8]) -> &str {
+        let mut out = Plain(buf, 0);
+        write!(out, "{}", value).unwrap();
+        let len = out.1;
+        core::str::from_utf8(&buf[..len]).unwrap()
+    }
+
+    #[test]
+    fn scripts_cover_the_parser() {
+        for shell in [Shell::Bash, Shell::Zsh, Shell::Fish] {
+            let mut buf = [0u8; 16384];
+            let script = render(Completions::new(shell), &mut buf);
+
+            assert!(Command::ALL
+                .iter()
+                .all(|c| script.contains(render(c, &mut [0u8; 16]))));
+            assert!(FLAGS.iter().all(|f| script.contains(&f.long[2..])));
+            assert!(Boss::KEYWORDS.iter().all(|k| script.contains(k)));
+            assert!(Rating::KEYWORDS.iter().all(|k| script.contains(k)));
+        }
+    }
+}
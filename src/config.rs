@@ -0,0 +1,223 @@
+use core::str::FromStr;
+
+use crate::{
+    color::Theme,
+    date::parse_utc_offset,
+    fs::ReadError,
+    opt::{Expected, OptError, Targets},
+    Boss, Fractal, Rating, Target,
+};
+
+const MAX_PATH: usize = 512;
+
+/// What can be set before the command line, by config key and `STAB_*`
+/// variable.
+const SETTINGS: [(&str, &str, Expected); 5] = [
+    ("boss", "STAB_BOSS\0", Expected::OneOf(&Boss::KEYWORDS)),
+    (
+        "fractal",
+        "STAB_FRACTAL\0",
+        Expected::OneOf(&Fractal::KEYWORDS),
+    ),
+    (
+        "threshold",
+        "STAB_THRESHOLD\0",
+        Expected::OneOf(&Rating::KEYWORDS),
+    ),
+    ("theme", "STAB_THEME\0", Expected::OneOf(&Theme::KEYWORDS)),
+    ("timezone", "STAB_TIMEZONE\0", Expected::UtcOffset),
+];
+
+pub const KEYS: [&str; 5] = ["boss", "fractal", "threshold", "theme", "timezone"];
+
+/// Defaults from the config file and the environment, which the command
+/// line overrides.
+#[derive(Debug, Copy, Clone)]
+pub struct Defaults {
+    pub targets: Option<Targets>,
+    pub threshold: Option<Rating>,
+    pub theme: Option<Theme>,
+    /// Minutes east of UTC.
+    pub utc_offset: Option<i16>,
+}
+
+impl Defaults {
+    pub const fn new() -> Self {
+        Self {
+            targets: None,
+            threshold: None,
+            theme: None,
+            utc_offset: None,
+        }
+    }
+
+    /// Reads `$XDG_CONFIG_HOME/stab/config`, then lets `STAB_*` variables
+    /// override it. A missing config file is the same as an empty one, but
+    /// one that can't be read is an error.
+    pub fn load() -> Result<Self, OptError> {
+        let mut path = [0u8; MAX_PATH];
+        let mut buf = [0u8; 4096];
+        let config = match config_path(&mut path).map(|p| crate::fs::read_to_buf(p, &mut buf)) {
+            Some(Ok(data)) => Self::parse(data)?,
+            Some(Err(ReadError::Missing)) | None => Self::new(),
+            Some(Err(e)) => return Err(OptError::ConfigFile(e)),
+        };
+
+        let mut env = Self::new();
+        for &(key, var, expected) in &SETTINGS {
+            if let Some(value) = getenv(var) {
+                env.set(key, value).map_err(|e| {
+                    e.unwrap_or(OptError::InvalidValue {
+                        flag: &var[..var.len() - 1],
+                        value,
+                        expected,
+                    })
+                })?;
+            }
+        }
+
+        Ok(env.or(config))
+    }
+
+    /// Parses a config file with one `key = value` setting per line. Bosses
+    /// and fractals add up and can also be given as a comma separated list.
+    /// Blank lines and lines starting with `#` are skipped.
+    pub fn parse(data: &[u8]) -> Result<Self, OptError> {
+        let mut defaults = Self::new();
+        for (i, line) in data.split(|&b| b == b'\n').enumerate() {
+            let line = line.trim_ascii();
+            if line.is_empty() || line[0] == b'#' {
+                continue;
+            }
+
+            let unknown = OptError::Config {
+                line: i + 1,
+                key: None,
+                expected: Expected::OneOf(&KEYS),
+            };
+            let (key, value) = core::str::from_utf8(line)
+                .ok()
+                .and_then(|line| line.split_once('='))
+                .ok_or(unknown)?;
+            let &(key, _, expected) = SETTINGS.iter().find(|s| s.0 == key.trim()).ok_or(unknown)?;
+            defaults.set(key, value.trim()).map_err(|e| {
+                e.unwrap_or(OptError::Config {
+                    line: i + 1,
+                    key: Some(key),
+                    expected,
+                })
+            })?;
+        }
+
+        Ok(defaults)
+    }
+
+    /// Fails with `None` when the value doesn't fit the key.
+    fn set(&mut self, key: &str, value: &str) -> Result<(), Option<OptError>> {
+        match key {
+            "boss" | "fractal" => {
+                let targets = self.targets.get_or_insert(Targets::new());
+                for name in value.split(',').map(str::trim) {
+                    let target = match key {
+                        "boss" => Target::Boss(Boss::from_str(name).map_err(|_| None)?),
+                        _ => Target::Fractal(Fractal::from_str(name).map_err(|_| None)?),
+                    };
+                    targets.push(target).map_err(Some)?;
+                }
+            }
+            "threshold" => self.threshold = Some(Rating::from_str(value).map_err(|_| None)?),
+            "theme" => self.theme = Some(Theme::from_str(value).map_err(|_| None)?),
+            "timezone" => self.utc_offset = Some(parse_utc_offset(value).ok_or(None)?),
+            _ => return Err(None),
+        }
+        Ok(())
+    }
+
+    /// Takes each setting from `self`, or from `earlier` where `self` has
+    /// none.
+    fn or(self, earlier: Self) -> Self {
+        Self {
+            targets: self.targets.or(earlier.targets),
+            threshold: self.threshold.or(earlier.threshold),
+            theme: self.theme.or(earlier.theme),
+            utc_offset: self.utc_offset.or(earlier.utc_offset),
+        }
+    }
+}
+
+impl Default for Defaults {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The value of an environment variable, `var` being nul terminated.
+fn getenv(var: &'static str) -> Option<&'static str> {
+    // the environment is left alone while stab runs
+    unsafe {
+        let value = libc::getenv(var.as_ptr() as *const _);
+        if value.is_null() {
+            return None;
+        }
+        core::ffi::CStr::from_ptr(value).to_str().ok()
+    }
+}
+
+/// `$XDG_CONFIG_HOME/stab/config`, or `$HOME/.config/stab/config` when
+/// that isn't set.
+fn config_path(buf: &mut [u8; MAX_PATH]) -> Option<&[u8]> {
+    let (base, rest) = match getenv("XDG_CONFIG_HOME\0").filter(|base| !base.is_empty()) {
+        Some(base) => (base, "/stab/config"),
+        None => (getenv("HOME\0")?, "/.config/stab/config"),
+    };
+
+    let len = base.len() + rest.len();
+    if len > buf.len() {
+        return None;
+    }
+    buf[..base.len()].copy_from_slice(base.as_bytes());
+    buf[base.len()..len].copy_from_slice(rest.as_bytes());
+    Some(&buf[..len])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_file() {
+        let config = b"# defaults\n\
+            boss = arkk, ai\n\
+            fractal=volcanic\n\
+            threshold = good\n\
+            theme = plain\n\
+            timezone = +02:00\n";
+        let defaults = Defaults::parse(config).unwrap();
+        assert_eq!(defaults.targets.map(|t| t.len()), Some(3));
+        assert_eq!(defaults.threshold, Some(Rating::Good));
+        assert_eq!(defaults.theme, Some(Theme::Plain));
+        assert_eq!(defaults.utc_offset, Some(120));
+
+        assert_eq!(
+            Defaults::parse(b"\nthreshold = meh\n").err(),
+            Some(OptError::Config {
+                line: 2,
+                key: Some("threshold"),
+                expected: Expected::OneOf(&Rating::KEYWORDS),
+            })
+        );
+        assert_eq!(
+            Defaults::parse(b"colour = red").err(),
+            Some(OptError::Config {
+                line: 1,
+                key: None,
+                expected: Expected::OneOf(&KEYS),
+            })
+        );
+
+        let later = Defaults::parse(b"threshold = perfect").unwrap();
+        let merged = later.or(defaults);
+        assert_eq!(merged.threshold, Some(Rating::Perfect));
+        assert_eq!(merged.utc_offset, Some(120));
+    }
+}
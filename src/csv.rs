@@ -0,0 +1,106 @@
+use core::fmt::{Display, Result, Write};
+
+/// Writes the fields of a comma separated row, quoting the ones that hold
+/// the separator, quotes or line breaks.
+pub struct Csv<'a, W: ?Sized> {
+    out: &'a mut W,
+    separator: char,
+    first: bool,
+}
+
+impl<'a, W> Csv<'a, W>
+where
+    W: Write + ?Sized,
+{
+    pub fn new(out: &'a mut W) -> Self {
+        Self::with_separator(out, ',')
+    }
+
+    /// Separates the fields with `separator` instead, like `\t` for TSV.
+    pub fn with_separator(out: &'a mut W, separator: char) -> Self {
+        Self {
+            out,
+            separator,
+            first: true,
+        }
+    }
+
+    pub fn field(&mut self, value: impl Display) -> Result {
+        if !self.first {
+            self.out.write_char(self.separator)?;
+        }
+        self.first = false;
+
+        let mut check = NeedsQuotes(self.separator, false);
+        write!(check, "{}", value)?;
+        if check.1 {
+            self.out.write_char('"')?;
+            write!(Quoted(&mut *self.out), "{}", value)?;
+            self.out.write_char('"')
+        } else {
+            write!(self.out, "{}", value)
+        }
+    }
+}
+
+struct NeedsQuotes(char, bool);
+
+impl Write for NeedsQuotes {
+    fn write_str(&mut self, s: &str) -> Result {
+        self.1 |= s.contains(&[self.0, '"', '\n', '\r'][..]);
+        Ok(())
+    }
+}
+
+/// Doubles quotes, for use inside a quoted field.
+struct Quoted<'a, W: ?Sized>(&'a mut W);
+
+impl<W> Write for Quoted<'_, W>
+where
+    W: Write + ?Sized,
+{
+    fn write_str(&mut self, s: &str) -> Result {
+        for (i, part) in s.split('"').enumerate() {
+            if i > 0 {
+                self.0.write_str("\"\"")?;
+            }
+            self.0.write_str(part)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::Plain;
+
+    #[test]
+    fn quoting() {
+        let mut buf = [0u8; 64];
+        let mut out = Plain(&mut buf, 0);
+        let mut csv = Csv::new(&mut out);
+        csv.field("plain").unwrap();
+        csv.field("No Pain, No Gain").unwrap();
+        csv.field("say \"hi\"").unwrap();
+        csv.field(3).unwrap();
+        let len = out.1;
+
+        assert_eq!(
+            core::str::from_utf8(&buf[..len]).unwrap(),
+            r#"plain,"No Pain, No Gain","say ""hi""",3"#
+        );
+
+        let mut buf = [0u8; 64];
+        let mut out = Plain(&mut buf, 0);
+        let mut tsv = Csv::with_separator(&mut out, '\t');
+        tsv.field("No Pain, No Gain").unwrap();
+        tsv.field("a\tb").unwrap();
+        let len = out.1;
+
+        assert_eq!(
+            core::str::from_utf8(&buf[..len]).unwrap(),
+            "No Pain, No Gain\t\"a\tb\""
+        );
+    }
+}
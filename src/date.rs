@@ -1,10 +1,13 @@
 // use std::time::SystemTime;
+use core::sync::atomic::{AtomicI32, Ordering};
 
 const FEB_28: u16 = 58;
 const DEC_31: u16 = 365;
 const UNIX_START_YEAR: u16 = 1970;
+const LEAP_CYCLE_DAYS: usize = 4 * 365 + 1;
+pub const MAX_WINDOW: usize = 366;
 
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Date {
     year: u16,
     day: u16,
@@ -34,24 +37,41 @@ fn unix_timestamp() -> u64 {
     unix.tv_sec as u64
 }
 
-impl Date {
-    pub fn today() -> Self {
-        let mut t = unix_timestamp();
-        let mut year = UNIX_START_YEAR;
+// the offset is read once from the config and used by every `today`
+static UTC_OFFSET: AtomicI32 = AtomicI32::new(0);
 
-        loop {
-            let length = year_length(year);
-            if t < length {
-                break;
-            }
-            t -= length;
-            year += 1;
-        }
+/// Sets the offset from UTC, in minutes, that decides which day is today.
+pub fn set_utc_offset(minutes: i16) {
+    UTC_OFFSET.store(minutes as i32, Ordering::Relaxed);
+}
 
-        let day = (t / (60 * 60 * 24)) as u16;
-        let day = normalize_leap_year(year, day);
+/// Parses `utc` or an offset like `+2`, `-05` or `+05:30` into minutes.
+pub fn parse_utc_offset(s: &str) -> Option<i16> {
+    if s.eq_ignore_ascii_case("utc") || s == "Z" {
+        return Some(0);
+    }
 
-        Self { year, day }
+    let (sign, rest) = match s.as_bytes().first()? {
+        b'+' => (1, &s[1..]),
+        b'-' => (-1, &s[1..]),
+        _ => return None,
+    };
+    let (hours, minutes) = rest.split_once(':').unwrap_or((rest, "0"));
+    if hours.is_empty() || hours.len() > 2 || minutes.is_empty() || minutes.len() > 2 {
+        return None;
+    }
+    let hours = hours.parse::<i16>().ok()?;
+    let minutes = minutes.parse::<i16>().ok()?;
+    if hours > 14 || minutes >= 60 {
+        return None;
+    }
+    Some(sign * (hours * 60 + minutes))
+}
+
+impl Date {
+    pub fn today() -> Self {
+        let offset = UTC_OFFSET.load(Ordering::Relaxed) as i64 * 60;
+        DateTime::from_unix(unix_timestamp().saturating_add_signed(offset)).date
     }
 
     pub const fn from_ymd(year: u16, month: u8, day: u8) -> Self {
@@ -100,6 +120,27 @@ impl Date {
         self.year
     }
 
+    /// Day of the year counting from 0, without the gap left for 29 February
+    /// in non-leap years.
+    pub const fn ordinal(&self) -> u16 {
+        if !is_leap_year(self.year) && self.day > FEB_28 {
+            self.day - 1
+        } else {
+            self.day
+        }
+    }
+
+    pub const fn weekday(&self) -> Weekday {
+        // 1970-01-01 was a thursday
+        let mut days = self.ordinal() as u64 + 3;
+        let mut year = UNIX_START_YEAR;
+        while year < self.year {
+            days += year_length(year) / (60 * 60 * 24);
+            year += 1;
+        }
+        Weekday::from_u8((days % 7) as u8)
+    }
+
     pub const fn successor(&self) -> Self {
         let year = self.year;
         let day = self.day;
@@ -117,6 +158,93 @@ impl Date {
     }
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Weekday {
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday,
+}
+
+impl Weekday {
+    const fn from_u8(n: u8) -> Self {
+        use Weekday::*;
+
+        match n % 7 {
+            0 => Monday,
+            1 => Tuesday,
+            2 => Wednesday,
+            3 => Thursday,
+            4 => Friday,
+            5 => Saturday,
+            _ => Sunday,
+        }
+    }
+}
+
+impl core::str::FromStr for Weekday {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use Weekday::*;
+
+        Ok(match s {
+            "mon" | "monday" => Monday,
+            "tue" | "tuesday" => Tuesday,
+            "wed" | "wednesday" => Wednesday,
+            "thu" | "thursday" => Thursday,
+            "fri" | "friday" => Friday,
+            "sat" | "saturday" => Saturday,
+            "sun" | "sunday" => Sunday,
+            _ => return Err(()),
+        })
+    }
+}
+
+impl core::fmt::Display for Weekday {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        use Weekday::*;
+
+        let name = match self {
+            Monday => "Mon",
+            Tuesday => "Tue",
+            Wednesday => "Wed",
+            Thursday => "Thu",
+            Friday => "Fri",
+            Saturday => "Sat",
+            Sunday => "Sun",
+        };
+
+        f.pad(name)
+    }
+}
+
+impl core::str::FromStr for Date {
+    type Err = ();
+
+    /// Parses `YYYY-MM-DD`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.splitn(3, '-');
+        let mut next = || parts.next().ok_or(());
+        let year = next()?.parse::<u16>().map_err(|_| ())?;
+        let month = next()?.parse::<u8>().map_err(|_| ())?;
+        let day = next()?.parse::<u8>().map_err(|_| ())?;
+
+        if year < UNIX_START_YEAR
+            || !(1..=12).contains(&month)
+            || day == 0
+            || day > days_per_month(year, month - 1)
+        {
+            return Err(());
+        }
+
+        Ok(Self::from_ymd(year, month, day))
+    }
+}
+
 impl core::fmt::Display for Date {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         let (year, month, day) = self.ymd();
@@ -124,8 +252,29 @@ impl core::fmt::Display for Date {
     }
 }
 
+/// Every day from `from` up to and including `to`.
+pub fn days_between(from: Date, to: Date) -> impl Iterator<Item = Date> {
+    let mut date = from;
+    core::iter::from_fn(move || {
+        if date > to {
+            return None;
+        }
+        let ret = date;
+        date = date.successor();
+        Some(ret)
+    })
+}
+
+/// One full cycle of the schedule, 29 February included.
+pub fn dataset_days() -> impl Iterator<Item = Date> {
+    days_between(Date::from_ymd(2020, 1, 1), Date::from_ymd(2020, 12, 31))
+}
+
 pub fn future_days() -> impl Iterator<Item = Date> {
-    let mut date = Date::today();
+    days_from(Date::today())
+}
+
+fn days_from(mut date: Date) -> impl Iterator<Item = Date> {
     core::iter::from_fn(move || {
         let ret = date;
         date = date.successor();
@@ -133,6 +282,114 @@ pub fn future_days() -> impl Iterator<Item = Date> {
     })
 }
 
+/// The next time each day of the schedule comes around, in date order.
+/// 29 February can be up to four years away.
+pub fn schedule_days() -> impl Iterator<Item = Date> {
+    schedule_days_from(Date::today())
+}
+
+fn schedule_days_from(from: Date) -> impl Iterator<Item = Date> {
+    let mut seen = [false; MAX_WINDOW];
+    days_from(from)
+        .take(LEAP_CYCLE_DAYS)
+        .filter(move |d| !core::mem::replace(&mut seen[d.day as usize], true))
+}
+
+/// Upcoming days for which `pred` holds. The schedule repeats every year, so
+/// this gives up once a full leap cycle goes by without a match.
+pub fn future_days_where(mut pred: impl FnMut(Date) -> bool) -> impl Iterator<Item = Date> {
+    let mut days = future_days();
+    core::iter::from_fn(move || {
+        for _ in 0..LEAP_CYCLE_DAYS {
+            let date = days.next()?;
+            if pred(date) {
+                return Some(date);
+            }
+        }
+        None
+    })
+}
+
+/// From today up to the day before today's date next year, so no day of the
+/// schedule comes up twice.
+pub fn year_ahead() -> (Date, Date) {
+    year_ahead_from(Date::today())
+}
+
+fn year_ahead_from(today: Date) -> (Date, Date) {
+    // stop short of today's date next year, or of 1 March after 29 February
+    let day = if today.day == FEB_28 + 1 {
+        today.day + 1
+    } else {
+        today.day
+    };
+    let next = Date {
+        year: today.year + 1,
+        day,
+    };
+    (
+        today,
+        days_from(today).take_while(|&d| d < next).last().unwrap(),
+    )
+}
+
+/// The next `window` days ranked by `key`, best first. Days without a key
+/// are left out, and ties go to the earlier day.
+pub fn ranked_days<K>(
+    window: usize,
+    mut key: impl FnMut(Date) -> Option<K>,
+) -> impl Iterator<Item = (Date, K)>
+where
+    K: Ord + Copy,
+{
+    let mut ranked = [None; MAX_WINDOW];
+    let mut len = 0;
+
+    for date in future_days().take(window.min(MAX_WINDOW)) {
+        if let Some(k) = key(date) {
+            ranked[len] = Some((date, k));
+            len += 1;
+        }
+    }
+
+    ranked[..len].sort_unstable_by(|a, b| {
+        let (a, b) = (a.unwrap(), b.unwrap());
+        b.1.cmp(&a.1).then(a.0.cmp(&b.0))
+    });
+    IntoIterator::into_iter(ranked).take(len).flatten()
+}
+
+/// Consecutive days that all did or all didn't pass a check.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Run {
+    pub first: Date,
+    pub last: Date,
+    pub days: u16,
+    pub passed: bool,
+}
+
+/// Splits `days` into alternating runs of days passing and failing `pred`.
+pub fn runs(
+    days: impl Iterator<Item = Date>,
+    mut pred: impl FnMut(Date) -> bool,
+) -> impl Iterator<Item = Run> {
+    let mut days = days.map(move |d| (d, pred(d))).peekable();
+    core::iter::from_fn(move || {
+        let (first, passed) = days.next()?;
+        let mut run = Run {
+            first,
+            last: first,
+            days: 1,
+            passed,
+        };
+        while let Some((date, _)) = days.next_if(|&(_, p)| p == passed) {
+            run.last = date;
+            run.days += 1;
+        }
+        Some(run)
+    })
+}
+
 // impl Year {
 //     const fn leap(&self) -> bool {
 //         (self.0 % 4 == 0) && (self.0 % 100 != 0 || self.0 % 400 == 0)
@@ -150,6 +407,45 @@ const fn is_leap_year(year: u16) -> bool {
     (year % 4 == 0) && (year % 100 != 0 || year % 400 == 0)
 }
 
+/// A moment in UTC, to the second.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct DateTime {
+    pub date: Date,
+    /// Seconds since midnight.
+    pub seconds: u32,
+}
+
+impl DateTime {
+    /// The time in UTC, whatever `set_utc_offset` was given.
+    pub fn now() -> Self {
+        Self::from_unix(unix_timestamp())
+    }
+
+    const fn from_unix(mut t: u64) -> Self {
+        let mut year = UNIX_START_YEAR;
+        loop {
+            let length = year_length(year);
+            if t < length {
+                break;
+            }
+            t -= length;
+            year += 1;
+        }
+
+        let day = (t / (60 * 60 * 24)) as u16;
+        let day = normalize_leap_year(year, day);
+        Self {
+            date: Date { year, day },
+            seconds: (t % (60 * 60 * 24)) as u32,
+        }
+    }
+
+    pub const fn hms(&self) -> (u8, u8, u8) {
+        let s = self.seconds;
+        ((s / 3600) as u8, (s / 60 % 60) as u8, (s % 60) as u8)
+    }
+}
+
 const fn year_length(year: u16) -> u64 {
     if !is_leap_year(year) {
         60 * 60 * 24 * 365
@@ -229,6 +525,122 @@ mod tests {
         assert_eq!(day, 1);
     }
 
+    #[test]
+    fn weekdays() {
+        assert_eq!(Date::from_ymd(1970, 1, 1).weekday(), Weekday::Thursday);
+        assert_eq!(Date::from_ymd(2021, 1, 1).weekday(), Weekday::Friday);
+        assert_eq!(Date::from_ymd(2021, 3, 1).weekday(), Weekday::Monday);
+        assert_eq!(Date::from_ymd(2020, 2, 29).weekday(), Weekday::Saturday);
+        assert_eq!(Date::from_ymd(2020, 12, 31).weekday(), Weekday::Thursday);
+    }
+
+    #[test]
+    fn parse_dates() {
+        use core::str::FromStr;
+
+        assert_eq!(Date::from_str("2021-03-01"), Ok(Date::from_ymd(2021, 3, 1)));
+        assert_eq!(
+            Date::from_str("2020-02-29"),
+            Ok(Date::from_ymd(2020, 2, 29))
+        );
+        assert!(Date::from_str("2021-02-29").is_err());
+        assert!(Date::from_str("2021-13-01").is_err());
+        assert!(Date::from_str("2021-03").is_err());
+        assert_eq!(dataset_days().count(), 366);
+    }
+
+    #[test]
+    fn year_ahead_has_each_day_once() {
+        let year = |from, to| days_between(from, to).count();
+
+        let (from, to) = year_ahead_from(Date::from_ymd(2026, 10, 19));
+        assert_eq!(to, Date::from_ymd(2027, 10, 18));
+        assert_eq!(year(from, to), 365);
+
+        let (from, to) = year_ahead_from(Date::from_ymd(2027, 10, 19));
+        assert_eq!(to, Date::from_ymd(2028, 10, 18));
+        assert_eq!(year(from, to), 366);
+
+        let (from, to) = year_ahead_from(Date::from_ymd(2028, 2, 29));
+        assert_eq!(to, Date::from_ymd(2029, 2, 28));
+        assert_eq!(year(from, to), 366);
+    }
+
+    #[test]
+    fn schedule_days_cover_the_dataset() {
+        let mut days = schedule_days_from(Date::from_ymd(2026, 10, 19));
+        assert_eq!(days.next(), Some(Date::from_ymd(2026, 10, 19)));
+
+        let mut last = Date::from_ymd(2026, 10, 18);
+        let mut count = 1;
+        let mut leap_day = None;
+        for date in days {
+            assert!(date > last);
+            if date.month_day() == (2, 29) {
+                leap_day = Some(date);
+            }
+            last = date;
+            count += 1;
+        }
+        assert_eq!(count, 366);
+        assert_eq!(leap_day, Some(Date::from_ymd(2028, 2, 29)));
+    }
+
+    #[test]
+    fn ranking() {
+        assert_eq!(ranked_days(30, |d| Some(d.day() % 7)).count(), 30);
+
+        let mut prev = None;
+        for (date, key) in ranked_days(30, |d| Some(d.day() % 7)) {
+            assert_eq!(key, date.day() % 7);
+            if let Some((prev_date, prev_key)) = prev {
+                assert!(prev_key > key || (prev_key == key && prev_date < date));
+            }
+            prev = Some((date, key));
+        }
+
+        assert_eq!(ranked_days(30, |_| None::<u8>).count(), 0);
+        assert_eq!(ranked_days(1000, Some).count(), MAX_WINDOW);
+    }
+
+    #[test]
+    fn utc_offsets() {
+        assert_eq!(parse_utc_offset("utc"), Some(0));
+        assert_eq!(parse_utc_offset("+2"), Some(120));
+        assert_eq!(parse_utc_offset("-05:30"), Some(-330));
+        assert_eq!(parse_utc_offset("+15"), None);
+        assert_eq!(parse_utc_offset("2"), None);
+        assert_eq!(parse_utc_offset("+1:"), None);
+    }
+
+    #[test]
+    fn streaks() {
+        let days = days_between(Date::from_ymd(2021, 2, 25), Date::from_ymd(2021, 3, 6));
+        let mut runs = runs(days, |d| d.month_day().1 % 5 != 0);
+
+        let run = runs.next().unwrap();
+        assert_eq!((run.days, run.passed), (1, false));
+        // across the end of february
+        let run = runs.next().unwrap();
+        assert_eq!(run.first, Date::from_ymd(2021, 2, 26));
+        assert_eq!(run.last, Date::from_ymd(2021, 3, 4));
+        assert_eq!((run.days, run.passed), (7, true));
+        let run = runs.next().unwrap();
+        assert_eq!((run.first, run.days, run.passed), (run.last, 1, false));
+        let run = runs.next().unwrap();
+        assert_eq!((run.first, run.days, run.passed), (run.last, 1, true));
+        assert_eq!(runs.next(), None);
+    }
+
+    #[test]
+    fn date_time() {
+        // 2021-03-01 12:34:56 UTC
+        let time = DateTime::from_unix(1_614_602_096);
+        assert_eq!(time.date, Date::from_ymd(2021, 3, 1));
+        assert_eq!(time.hms(), (12, 34, 56));
+        assert_eq!(DateTime::from_unix(0).date, Date::from_ymd(1970, 1, 1));
+    }
+
     #[test]
     fn time() {
         use libc_print::std_name::println;
@@ -0,0 +1,709 @@
+use core::{
+    cell::Cell,
+    fmt::{Display, Write},
+};
+
+use crate::{
+    color::{ColorByRater, ColorRating, DefaultColors},
+    csv::Csv,
+    json::Json,
+    set::{Dailylike, Instabs},
+    AltPolicy, AltRateable, Boss, Date, Fractal, HasInstabs, Parsable, Rating, Set, Target,
+};
+
+/// How days are printed, picked with `--format`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Format {
+    /// Coloured text for people.
+    Text,
+    /// One JSON object per day and line.
+    Json,
+    /// A header and one comma separated row per day.
+    Csv,
+    /// Like `Csv`, but with tabs, which spreadsheets take when pasted.
+    Tsv,
+    /// A heading and a list per day.
+    Markdown,
+    /// Coloured text in Discord's ```ansi blocks, split into messages.
+    Discord,
+}
+
+impl Format {
+    pub const KEYWORDS: [&'static str; 6] = ["text", "json", "csv", "tsv", "markdown", "discord"];
+
+    /// What separates the fields of `CsvHeader` and `CsvDay`, for the
+    /// formats that use them.
+    pub const fn separator(&self) -> Option<char> {
+        match self {
+            Format::Csv => Some(','),
+            Format::Tsv => Some('\t'),
+            _ => None,
+        }
+    }
+}
+
+impl core::str::FromStr for Format {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "text" => Format::Text,
+            "json" => Format::Json,
+            "csv" => Format::Csv,
+            "tsv" => Format::Tsv,
+            "markdown" => Format::Markdown,
+            "discord" => Format::Discord,
+            _ => return Err(()),
+        })
+    }
+}
+
+impl core::fmt::Display for Format {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let name = match self {
+            Format::Text => "text",
+            Format::Json => "json",
+            Format::Csv => "csv",
+            Format::Tsv => "tsv",
+            Format::Markdown => "markdown",
+            Format::Discord => "discord",
+        };
+
+        f.pad(name)
+    }
+}
+
+/// A day as a JSON object: its rating, the rating by each of `ratings`,
+/// and every CM and daily with its instabilities.
+pub struct JsonDay<I> {
+    date: Date,
+    alt_policy: AltPolicy,
+    ratings: I,
+}
+
+impl<I> JsonDay<I>
+where
+    I: Iterator<Item = (Target, Rating)> + Clone,
+{
+    pub fn new(date: Date, alt_policy: AltPolicy, ratings: I) -> Self {
+        Self {
+            date,
+            alt_policy,
+            ratings,
+        }
+    }
+}
+
+impl<I> core::fmt::Display for JsonDay<I>
+where
+    I: Iterator<Item = (Target, Rating)> + Clone,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let set = Set::parse(self.date);
+        let day = set.rate_day(self.alt_policy);
+
+        let mut json = Json::new(f);
+        json.object()?;
+        json.field("date", self.date)?;
+        json.field("rating", day.rating)?;
+        json.key("score")?;
+        json.number(day.score as u32)?;
+        json.key("ratings")?;
+        json.object()?;
+        for (target, rating) in self.ratings.clone() {
+            json.field(target, rating)?;
+        }
+        json.end()?;
+
+        json.key("fractals")?;
+        json.array()?;
+        for daily in set.all_iter() {
+            let fractal = *daily.fractal();
+            json.object()?;
+            json.field("fractal", fractal)?;
+            json.key("cm")?;
+            json.boolean(Boss::ALL.iter().any(|b| b.home() == fractal))?;
+            json.field("rating", daily.rate_with_alt(&fractal, self.alt_policy))?;
+            json.key("instabilities")?;
+            json.array()?;
+            for instab in daily.instabs().iter() {
+                json.string(instab)?;
+            }
+            json.end()?;
+            json.key("alt")?;
+            match daily.alt() {
+                Some(alt) => {
+                    json.array()?;
+                    for instab in alt.iter() {
+                        json.string(instab)?;
+                    }
+                    json.end()?;
+                }
+                None => json.null()?,
+            }
+            json.end()?;
+        }
+        json.end()?;
+        json.end()
+    }
+}
+
+const CMS: [Fractal; 3] = [
+    Fractal::Nightmare,
+    Fractal::ShatteredObservatory,
+    Fractal::SunquaPeak,
+];
+
+/// The T4 dailies each CSV row has.
+const DAILIES: usize = 3;
+
+/// The first row of `--format csv` and `tsv`, naming the columns of
+/// `CsvDay`.
+pub struct CsvHeader {
+    separator: char,
+}
+
+impl CsvHeader {
+    pub const fn new(separator: char) -> Self {
+        Self { separator }
+    }
+}
+
+impl core::fmt::Display for CsvHeader {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let mut csv = Csv::with_separator(f, self.separator);
+        csv.field("date")?;
+        for i in 1..=DAILIES {
+            csv.field(format_args!("daily {}", i))?;
+            csv.field(format_args!("daily {} instabilities", i))?;
+        }
+        for cm in &CMS {
+            csv.field(format_args!("{} instabilities", cm))?;
+        }
+        for boss in &Boss::ALL {
+            csv.field(format_args!("{} rating", boss))?;
+        }
+        for i in 1..=DAILIES {
+            csv.field(format_args!("daily {} rating", i))?;
+        }
+        Ok(())
+    }
+}
+
+/// A day as a CSV row: the T4 dailies and the CMs with their
+/// instabilities, then the rating by each boss and of each daily.
+pub struct CsvDay {
+    date: Date,
+    alt_policy: AltPolicy,
+    separator: char,
+}
+
+impl CsvDay {
+    pub const fn new(date: Date, alt_policy: AltPolicy, separator: char) -> Self {
+        Self {
+            date,
+            alt_policy,
+            separator,
+        }
+    }
+}
+
+/// Instabilities followed by the alternate ones, like `export` shows them.
+pub(crate) struct WithAlt<'a>(pub &'a Instabs, pub Option<&'a Instabs>);
+
+impl core::fmt::Display for WithAlt<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", self.0)?;
+        if let Some(alt) = self.1 {
+            write!(f, " | {}", alt)?;
+        }
+        Ok(())
+    }
+}
+
+impl core::fmt::Display for CsvDay {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let set = Set::parse(self.date);
+        let find = |fractal| set.all_iter().find(|d| *d.fractal() == fractal);
+
+        let mut csv = Csv::with_separator(f, self.separator);
+        csv.field(self.date)?;
+        for daily in set.t4_iter() {
+            csv.field(daily.fractal())?;
+            csv.field(WithAlt(daily.instabs(), daily.alt()))?;
+        }
+        for &cm in &CMS {
+            match find(cm) {
+                Some(daily) => csv.field(WithAlt(daily.instabs(), daily.alt()))?,
+                None => csv.field("")?,
+            }
+        }
+        for boss in &Boss::ALL {
+            match find(boss.home()) {
+                Some(daily) => csv.field(daily.rate_with_alt(boss, self.alt_policy))?,
+                None => csv.field("")?,
+            }
+        }
+        for daily in set.t4_iter() {
+            csv.field(daily.rate_with_alt(daily.fractal(), self.alt_policy))?;
+        }
+        Ok(())
+    }
+}
+
+/// A rating as an emoji, which reads the same with or without colours.
+struct Emoji(Rating);
+
+impl core::fmt::Display for Emoji {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let emoji = match self.0 {
+            Rating::Perfect => "\u{1f31f}",
+            Rating::Good => "\u{1f7e2}",
+            Rating::Playable => "\u{1f7e1}",
+            Rating::Bad => "\u{1f7e0}",
+            Rating::Unplayable => "\u{1f534}",
+        };
+
+        f.write_str(emoji)
+    }
+}
+
+/// A day in Markdown: a heading with its rating, the rating by each of
+/// `ratings`, and a list of every CM and daily with its instabilities.
+pub struct MarkdownDay<I> {
+    date: Date,
+    alt_policy: AltPolicy,
+    ratings: I,
+}
+
+impl<I> MarkdownDay<I>
+where
+    I: Iterator<Item = (Target, Rating)> + Clone,
+{
+    pub fn new(date: Date, alt_policy: AltPolicy, ratings: I) -> Self {
+        Self {
+            date,
+            alt_policy,
+            ratings,
+        }
+    }
+}
+
+impl<I> core::fmt::Display for MarkdownDay<I>
+where
+    I: Iterator<Item = (Target, Rating)> + Clone,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let set = Set::parse(self.date);
+        let rating = set.rate_day(self.alt_policy).rating;
+
+        write!(f, "### {}: {} {}", self.date, Emoji(rating), rating)?;
+        for (i, (target, rating)) in self.ratings.clone().enumerate() {
+            let sep = if i == 0 { "\n" } else { " \u{b7} " };
+            write!(f, "{}{}: **{}**", sep, target, rating)?;
+        }
+        for daily in set.all_iter() {
+            let rating = daily.rate_with_alt(daily.fractal(), self.alt_policy);
+            write!(
+                f,
+                "\n- **{}** {}: {}",
+                daily.fractal(),
+                Emoji(rating),
+                WithAlt(daily.instabs(), daily.alt())
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// A day for Discord, laid out like the text output with emoji next to the
+/// ratings. Meant for the `basic` theme, inside a message from
+/// `Messages::record`.
+pub struct DiscordDay<I> {
+    date: Date,
+    alt_policy: AltPolicy,
+    ratings: I,
+}
+
+impl<I> DiscordDay<I>
+where
+    I: Iterator<Item = (Target, Rating)> + Clone,
+{
+    pub fn new(date: Date, alt_policy: AltPolicy, ratings: I) -> Self {
+        Self {
+            date,
+            alt_policy,
+            ratings,
+        }
+    }
+}
+
+impl<I> core::fmt::Display for DiscordDay<I>
+where
+    I: Iterator<Item = (Target, Rating)> + Clone,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let set = Set::parse(self.date);
+        let rating = set.rate_day(self.alt_policy).rating;
+
+        write!(
+            f,
+            "{} {} {}",
+            ColorRating::<DefaultColors>::color_by_rating(&self.date, rating),
+            Emoji(rating),
+            rating
+        )?;
+        for (target, rating) in self.ratings.clone() {
+            write!(
+                f,
+                "  {}: {} {}",
+                ColorRating::<DefaultColors>::color_by_rating(&target, rating),
+                Emoji(rating),
+                rating
+            )?;
+        }
+        for daily in set.all_iter() {
+            write!(
+                f,
+                "\n  {}",
+                daily
+                    .color_by_rater(*daily.fractal())
+                    .with_alt_policy(self.alt_policy)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Discord's limit on the characters of a message. Lengths are counted in
+/// bytes here, which are never fewer.
+pub const MESSAGE_LIMIT: usize = 2000;
+const OPEN: &str = "```ansi\n";
+const CLOSE: &str = "```\n";
+/// Ends a message and starts the next, with a form feed line between them.
+const NEXT_MESSAGE: &str = "```\n\x0c\n```ansi\n";
+
+/// Longest part of a line written at once. Well under what a message holds,
+/// so any part fits in a new one.
+const LINE_PART: usize = 256;
+
+/// How full the current Discord message is, as records are printed into
+/// messages of at most `MESSAGE_LIMIT` bytes.
+#[derive(Debug, Default)]
+pub struct Messages {
+    used: Cell<usize>,
+}
+
+impl Messages {
+    pub const fn new() -> Self {
+        Self { used: Cell::new(0) }
+    }
+
+    /// `record` and its line break, after the start of the first message, a
+    /// blank line after the previous record, or the end of a full message
+    /// and the start of the next. A record longer than a message is split
+    /// between its lines. Meant to be written once.
+    pub fn record<R: Display>(&self, record: R) -> Record<'_, R> {
+        Record {
+            messages: self,
+            record,
+        }
+    }
+
+    /// What ends the last message, if one was started.
+    pub fn end(&self) -> &'static str {
+        if self.used.get() > 0 {
+            CLOSE
+        } else {
+            ""
+        }
+    }
+}
+
+/// A record in a Discord message, from `Messages::record`.
+pub struct Record<'a, R> {
+    messages: &'a Messages,
+    record: R,
+}
+
+impl<R: Display> Display for Record<'_, R> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        struct Len(usize);
+
+        impl Write for Len {
+            fn write_str(&mut self, s: &str) -> core::fmt::Result {
+                self.0 += s.len();
+                Ok(())
+            }
+        }
+
+        let mut len = Len(0);
+        write!(len, "{}", self.record)?;
+        let len = len.0 + 1;
+        let used = self.messages.used.get();
+        let (before, used) = if used == 0 {
+            (OPEN, OPEN.len())
+        } else if used + 1 + len + CLOSE.len() <= MESSAGE_LIMIT {
+            ("\n", used + 1)
+        } else {
+            (NEXT_MESSAGE, OPEN.len())
+        };
+        f.write_str(before)?;
+
+        let mut lines = Lines {
+            out: f,
+            used,
+            part: [0; LINE_PART],
+            len: 0,
+        };
+        writeln!(lines, "{}", self.record)?;
+        lines.flush()?;
+        self.messages.used.set(lines.used);
+        Ok(())
+    }
+}
+
+/// Writes through line by line, ending the message and starting the next
+/// before a line that doesn't fit.
+struct Lines<'a, 'b> {
+    out: &'a mut core::fmt::Formatter<'b>,
+    used: usize,
+    part: [u8; LINE_PART],
+    len: usize,
+}
+
+impl Lines<'_, '_> {
+    fn flush(&mut self) -> core::fmt::Result {
+        if self.used + self.len + CLOSE.len() > MESSAGE_LIMIT {
+            self.out.write_str(NEXT_MESSAGE)?;
+            self.used = OPEN.len();
+        }
+        // only whole chars are ever added
+        let part = core::str::from_utf8(&self.part[..self.len]).unwrap();
+        self.out.write_str(part)?;
+        self.used += self.len;
+        self.len = 0;
+        Ok(())
+    }
+}
+
+impl Write for Lines<'_, '_> {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        for c in s.chars() {
+            if self.len + c.len_utf8() > LINE_PART {
+                self.flush()?;
+            }
+            c.encode_utf8(&mut self.part[self.len..]);
+            self.len += c.len_utf8();
+            if c == '\n' {
+                self.flush()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::Plain;
+    use core::fmt::Write;
+
+    #[test]
+    fn json_day() {
+        let date = Date::from_ymd(2021, 3, 1);
+        let ratings = [(Target::Boss(Boss::Arkk), Rating::Good)];
+        let mut buf = [0u8; 2048];
+        let mut out = Plain(&mut buf, 0);
+        write!(
+            out,
+            "{}",
+            JsonDay::new(date, AltPolicy::Worst, ratings.iter().copied())
+        )
+        .unwrap();
+        let len = out.1;
+        let json = core::str::from_utf8(&buf[..len]).unwrap();
+
+        assert!(json.starts_with(r#"{"date":"2021-03-01","rating":"#));
+        assert!(json.contains(r#""ratings":{"Arkk":"Good"}"#));
+        assert_eq!(json.matches(r#""cm":true"#).count(), 3);
+        assert_eq!(json.matches('{').count(), json.matches('}').count());
+    }
+
+    /// Splits `row` like a CSV reader would, dropping the quotes around
+    /// fields, which never hold quotes themselves here.
+    fn fields<'a>(row: &'a str, separator: char, out: &mut [&'a str; 32]) -> usize {
+        let mut len = 0;
+        let mut rest = row;
+        loop {
+            let (field, next) = match rest.strip_prefix('"') {
+                Some(quoted) => {
+                    let end = quoted.find('"').unwrap();
+                    let next = &quoted[end + 1..];
+                    assert!(next.is_empty() || next.starts_with(separator));
+                    (&quoted[..end], next.strip_prefix(separator))
+                }
+                None => match rest.split_once(separator) {
+                    Some((field, next)) => (field, Some(next)),
+                    None => (rest, None),
+                },
+            };
+            out[len] = field;
+            len += 1;
+            match next {
+                Some(next) => rest = next,
+                None => return len,
+            }
+        }
+    }
+
+    #[test]
+    fn csv_columns() {
+        const HEADER: &str = "date,daily 1,daily 1 instabilities,daily 2,\
+            daily 2 instabilities,daily 3,daily 3 instabilities,\
+            Nightmare instabilities,Shattered Observatory instabilities,\
+            Sunqua Peak instabilities,Mama rating,Siax rating,Ensolyss rating,\
+            Skorvald rating,Artsariiv rating,Arkk rating,Ai rating,\
+            daily 1 rating,daily 2 rating,daily 3 rating";
+
+        for separator in [',', '\t'] {
+            let mut buf = [0u8; 2048];
+            let mut out = Plain(&mut buf, 0);
+            writeln!(out, "{}", CsvHeader::new(separator)).unwrap();
+            let date = Date::from_ymd(2021, 3, 1);
+            write!(out, "{}", CsvDay::new(date, AltPolicy::Worst, separator)).unwrap();
+            let len = out.1;
+            let (header, row) = core::str::from_utf8(&buf[..len])
+                .unwrap()
+                .split_once('\n')
+                .unwrap();
+
+            let mut names = [""; 32];
+            let columns = fields(header, separator, &mut names);
+            assert_eq!(columns, HEADER.split(',').count());
+            assert!(names.iter().zip(HEADER.split(',')).all(|(a, b)| a == &b));
+
+            let mut values = [""; 32];
+            assert_eq!(fields(row, separator, &mut values), columns);
+            let value = |name| {
+                let i = names.iter().position(|&n| n == name).unwrap();
+                values[i]
+            };
+            assert_eq!(value("date"), "2021-03-01");
+            assert_eq!(value("daily 1"), "Nightmare");
+            assert_eq!(
+                value("Shattered Observatory instabilities"),
+                "Last Laugh; No Pain, No Gain; Frailty"
+            );
+            assert_eq!(value("Arkk rating"), "Unplayable");
+            assert_eq!(value("Ai rating"), "Good");
+        }
+    }
+
+    #[test]
+    fn markdown_day() {
+        let date = Date::from_ymd(2021, 3, 1);
+        let ratings = [(Target::Boss(Boss::Arkk), Rating::Good)];
+        let mut buf = [0u8; 2048];
+        let mut out = Plain(&mut buf, 0);
+        write!(
+            out,
+            "{}",
+            MarkdownDay::new(date, AltPolicy::Worst, ratings.iter().copied())
+        )
+        .unwrap();
+        let len = out.1;
+        let md = core::str::from_utf8(&buf[..len]).unwrap();
+
+        assert!(md.starts_with("### 2021-03-01: "));
+        assert!(md.contains("\nArkk: **Good**\n- **"));
+        assert_eq!(
+            md.lines().filter(|l| l.starts_with("- **")).count(),
+            Set::parse(date).all_iter().count()
+        );
+    }
+
+    /// The messages of `output`, checking each is a whole ```ansi block
+    /// within the limit.
+    fn split_messages(output: &str) -> impl Iterator<Item = &str> {
+        output.split("\x0c\n").map(|message| {
+            assert!(message.len() <= MESSAGE_LIMIT);
+            let inner = message.strip_prefix(OPEN).unwrap();
+            inner.strip_suffix(CLOSE).unwrap()
+        })
+    }
+
+    #[test]
+    fn messages_stay_under_the_limit() {
+        let mut buf = [0u8; 65536];
+        let mut out = Plain(&mut buf, 0);
+        let messages = Messages::new();
+        let ratings = [
+            (Target::Boss(Boss::Arkk), Rating::Good),
+            (Target::Boss(Boss::Ai), Rating::Bad),
+        ];
+        let mut date = Date::from_ymd(2021, 3, 1);
+        for _ in 0..30 {
+            let day = DiscordDay::new(date, AltPolicy::Worst, ratings.iter().copied());
+            write!(out, "{}", messages.record(day)).unwrap();
+            date = date.successor();
+        }
+        write!(out, "{}", messages.end()).unwrap();
+        let len = out.1;
+        let output = core::str::from_utf8(&buf[..len]).unwrap();
+
+        assert!(split_messages(output).count() > 1);
+        // days are never split, and each is there once
+        let mut days = 0;
+        for message in split_messages(output) {
+            for record in message.trim_end_matches('\n').split("\n\n") {
+                assert!(record.contains("Arkk"));
+                days += 1;
+            }
+        }
+        assert_eq!(days, 30);
+    }
+
+    #[test]
+    fn long_records_are_split() {
+        struct Long;
+
+        impl Display for Long {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                for i in 0..100 {
+                    writeln!(f, "line {:02}{:40}", i, "")?;
+                }
+                // longer than a message on its own
+                write!(f, "{:3000}", "end")
+            }
+        }
+
+        let mut buf = [0u8; 16384];
+        let mut out = Plain(&mut buf, 0);
+        let messages = Messages::new();
+        write!(out, "{}", messages.record("first")).unwrap();
+        write!(out, "{}", messages.record(Long)).unwrap();
+        write!(out, "{}", messages.end()).unwrap();
+        let len = out.1;
+        let output = core::str::from_utf8(&buf[..len]).unwrap();
+
+        assert!(split_messages(output).count() >= 4);
+        assert_eq!(split_messages(output).next(), Some("first\n"));
+        // lines stay whole where they fit
+        for message in split_messages(output) {
+            assert!(message.ends_with('\n') || message.ends_with(' '));
+        }
+
+        // nothing is lost or added between the messages
+        let mut expected = [0u8; 8192];
+        let mut out = Plain(&mut expected, 0);
+        write!(out, "first\n{}\n", Long).unwrap();
+        let len = out.1;
+        let mut rest = &expected[..len];
+        for message in split_messages(output) {
+            assert!(rest.starts_with(message.as_bytes()));
+            rest = &rest[message.len()..];
+        }
+        assert!(rest.is_empty());
+    }
+}
@@ -23,7 +23,68 @@ pub enum Fractal {
     SunquaPeak,
 }
 
+pub(crate) const FRACTAL_COUNT: usize = 21;
+
 impl Fractal {
+    /// What `from_str` accepts, in order.
+    pub const KEYWORDS: [&'static str; FRACTAL_COUNT] = [
+        "aetherblade",
+        "aquatic",
+        "mai",
+        "chaos",
+        "cliff",
+        "deepstone",
+        "molten-boss",
+        "furnace",
+        "nightmare",
+        "shattered",
+        "reef",
+        "snowblind",
+        "solid",
+        "swamp",
+        "thauma",
+        "twilight",
+        "uncat",
+        "dredge",
+        "urban",
+        "volcanic",
+        "sunqua",
+    ];
+
+    /// The keywords and the aliases players use.
+    fn keyword(s: &str) -> Option<Self> {
+        use Fractal::*;
+
+        Some(match s {
+            "aetherblade" => Aetherblade,
+            "aquatic" => AquaticRuins,
+            "mai" => CaptainMaiTrinBoss,
+            "chaos" => ChaosIsles,
+            "cliff" => Cliffside,
+            "deepstone" => Deepstone,
+            "molten-boss" => MoltenBoss,
+            "furnace" => MoltenFurnace,
+            "nightmare" | "nm" => Nightmare,
+            "shattered" | "so" => ShatteredObservatory,
+            "reef" => SirensReef,
+            "snowblind" => Snowblind,
+            "solid" => SolidOcean,
+            "swamp" => Swampland,
+            "thauma" => ThaumanovaReactor,
+            "twilight" => TwilightOasis,
+            "uncat" => Uncategorized,
+            "dredge" => UndergroundFacility,
+            "urban" => UrbanBattleground,
+            "volcanic" => Volcanic,
+            "sunqua" | "sp" => SunquaPeak,
+            _ => return None,
+        })
+    }
+
+    pub fn all() -> impl Iterator<Item = Fractal> + Clone {
+        (b'A'..b'A' + FRACTAL_COUNT as u8).filter_map(Fractal::from_u8)
+    }
+
     pub const fn from_u8(n: u8) -> Option<Self> {
         use Fractal::*;
         match n {
@@ -58,32 +119,15 @@ impl core::str::FromStr for Fractal {
     type Err = ();
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        use Fractal::*;
-
-        Ok(match s {
-            "aetherblade" => Aetherblade,
-            "aquatic" => AquaticRuins,
-            "mai" => CaptainMaiTrinBoss,
-            "chaos" => ChaosIsles,
-            "cliff" => Cliffside,
-            "deepstone" => Deepstone,
-            "molten boss" => MoltenBoss,
-            "furnace" => MoltenFurnace,
-            "nightmare" => Nightmare,
-            "shattered" => ShatteredObservatory,
-            "reef" => SirensReef,
-            "snowblind" => Snowblind,
-            "swamp" => Swampland,
-            "thauma" => ThaumanovaReactor,
-            "twilight" => TwilightOasis,
-            "uncat" => Uncategorized,
-            "dredge" => UndergroundFacility,
-            "urban" => UrbanBattleground,
-            "volcanic" => Volcanic,
-            "sunqua" => SunquaPeak,
+        crate::keyword::lookup(s, Self::all(), &Self::KEYWORDS, Self::keyword)
+    }
+}
 
-            _ => return Err(()),
-        })
+impl Fractal {
+    /// The fractal `s` names in full, without taking prefixes like
+    /// `from_str` does.
+    pub(crate) fn from_exact(s: &str) -> Option<Self> {
+        crate::keyword::exact(s, Self::all(), Self::keyword)
     }
 }
 
@@ -115,7 +159,7 @@ impl core::fmt::Display for Fractal {
             SunquaPeak => "Sunqua Peak",
         };
 
-        write!(f, "{}", name)
+        f.pad(name)
     }
 }
 
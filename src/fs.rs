@@ -0,0 +1,59 @@
+const MAX_PATH: usize = 512;
+
+/// Why a file couldn't be read.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ReadError {
+    Missing,
+    Unreadable,
+    TooLarge,
+}
+
+impl core::fmt::Display for ReadError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.pad(match self {
+            ReadError::Missing => "doesn't exist",
+            ReadError::Unreadable => "can't be read",
+            ReadError::TooLarge => "is too large",
+        })
+    }
+}
+
+/// Reads the whole file at `path` into `buf`, returning the filled part.
+/// Fails if the file can't be opened or doesn't fit.
+pub fn read_to_buf<'a>(path: &[u8], buf: &'a mut [u8]) -> Result<&'a [u8], ReadError> {
+    let mut c_path = [0u8; MAX_PATH];
+    if path.len() >= MAX_PATH || path.contains(&b'\0') {
+        return Err(ReadError::Unreadable);
+    }
+    c_path[..path.len()].copy_from_slice(path);
+
+    unsafe {
+        let file = libc::fopen(c_path.as_ptr() as *const _, b"rb\0".as_ptr() as *const _);
+        if file.is_null() {
+            // mode 0 only asks whether the file is there at all
+            return Err(if libc::access(c_path.as_ptr() as *const _, 0) == 0 {
+                ReadError::Unreadable
+            } else {
+                ReadError::Missing
+            });
+        }
+
+        let mut len = 0;
+        while len < buf.len() {
+            let read = libc::fread(buf[len..].as_mut_ptr() as *mut _, 1, buf.len() - len, file);
+            if read == 0 {
+                break;
+            }
+            len += read;
+        }
+
+        let complete = libc::feof(file) != 0 || libc::fgetc(file) == libc::EOF;
+        libc::fclose(file);
+
+        if complete {
+            Ok(&buf[..len])
+        } else {
+            Err(ReadError::TooLarge)
+        }
+    }
+}
@@ -0,0 +1,369 @@
+use core::fmt::{Display, Formatter, Result, Write};
+
+use crate::{
+    date::DateTime, format::WithAlt, set::Dailylike, Date, HasInstabs, Opts, Parsable, Rater,
+    Rating, Set, Target,
+};
+
+/// Longest line in octets before it is folded onto the next one.
+const LINE_LEN: usize = 75;
+
+/// Writes iCalendar properties as CRLF terminated lines, folding the long
+/// ones.
+pub struct Ics<'a, W: ?Sized> {
+    out: &'a mut W,
+}
+
+impl<'a, W> Ics<'a, W>
+where
+    W: Write + ?Sized,
+{
+    pub fn new(out: &'a mut W) -> Self {
+        Self { out }
+    }
+
+    /// A property whose value is written as it displays.
+    pub fn value(&mut self, name: &str, value: impl Display) -> Result {
+        let mut line = Folded::new(&mut *self.out);
+        write!(line, "{}:{}", name, value)?;
+        self.out.write_str("\r\n")
+    }
+
+    /// A property with a `TEXT` value, escaping what has to be.
+    pub fn text(&mut self, name: &str, value: impl Display) -> Result {
+        let mut line = Folded::new(&mut *self.out);
+        write!(line, "{}:", name)?;
+        write!(Escaped(&mut line), "{}", value)?;
+        self.out.write_str("\r\n")
+    }
+}
+
+/// Starts a continuation line whenever the current one would get too long.
+struct Folded<'a, W: ?Sized> {
+    out: &'a mut W,
+    len: usize,
+}
+
+impl<'a, W> Folded<'a, W>
+where
+    W: Write + ?Sized,
+{
+    fn new(out: &'a mut W) -> Self {
+        Self { out, len: 0 }
+    }
+}
+
+impl<W> Write for Folded<'_, W>
+where
+    W: Write + ?Sized,
+{
+    fn write_str(&mut self, s: &str) -> Result {
+        for c in s.chars() {
+            if self.len + c.len_utf8() > LINE_LEN {
+                self.out.write_str("\r\n ")?;
+                self.len = 1;
+            }
+            self.out.write_char(c)?;
+            self.len += c.len_utf8();
+        }
+        Ok(())
+    }
+}
+
+/// Escapes what is written for use in a `TEXT` value.
+struct Escaped<'a, W: ?Sized>(&'a mut W);
+
+impl<W> Write for Escaped<'_, W>
+where
+    W: Write + ?Sized,
+{
+    fn write_str(&mut self, s: &str) -> Result {
+        for c in s.chars() {
+            match c {
+                '\\' | ';' | ',' => {
+                    self.0.write_char('\\')?;
+                    self.0.write_char(c)?;
+                }
+                '\n' => self.0.write_str("\\n")?,
+                c => self.0.write_char(c)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A date as `DATE` value, like 20210301.
+struct Basic(Date);
+
+impl Display for Basic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let (year, month, day) = self.0.ymd();
+        write!(f, "{}{:02}{:02}", year, month, day)
+    }
+}
+
+/// A time as UTC `DATE-TIME` value, like 20210301T123456Z.
+struct BasicTime(DateTime);
+
+impl Display for BasicTime {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let (hours, minutes, seconds) = self.0.hms();
+        write!(
+            f,
+            "{}T{:02}{:02}{:02}Z",
+            Basic(self.0.date),
+            hours,
+            minutes,
+            seconds
+        )
+    }
+}
+
+/// A target's name in lower case with dashes, like shattered-observatory.
+struct Slug(Target);
+
+impl Display for Slug {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        struct Lower<'a, 'b>(&'a mut Formatter<'b>);
+
+        impl Write for Lower<'_, '_> {
+            fn write_str(&mut self, s: &str) -> Result {
+                for c in s.chars() {
+                    self.0.write_char(match c {
+                        ' ' => '-',
+                        c => c.to_ascii_lowercase(),
+                    })?;
+                }
+                Ok(())
+            }
+        }
+
+        write!(Lower(f), "{}", self.0)
+    }
+}
+
+/// The start of the calendar `ics` writes, before its events.
+pub struct IcsHeader;
+
+impl Display for IcsHeader {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let mut ics = Ics::new(f);
+        ics.value("BEGIN", "VCALENDAR")?;
+        ics.value("VERSION", "2.0")?;
+        ics.value(
+            "PRODID",
+            format_args!("-//stab//stab {}//EN", env!("CARGO_PKG_VERSION")),
+        )?;
+        ics.value("CALSCALE", "GREGORIAN")?;
+        ics.value("METHOD", "PUBLISH")?;
+        ics.text("X-WR-CALNAME", "Fractal CMs")
+    }
+}
+
+/// The end of the calendar, after its events.
+pub struct IcsFooter;
+
+impl Display for IcsFooter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        Ics::new(f).value("END", "VCALENDAR")
+    }
+}
+
+/// An all-day event for a target on a day, summing up its rating and
+/// instabilities, as written at `stamp`.
+pub struct IcsEvent {
+    date: Date,
+    target: Target,
+    rating: Rating,
+    stamp: DateTime,
+}
+
+impl IcsEvent {
+    pub const fn new(date: Date, target: Target, rating: Rating, stamp: DateTime) -> Self {
+        Self {
+            date,
+            target,
+            rating,
+            stamp,
+        }
+    }
+}
+
+impl Display for IcsEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let set = Set::parse(self.date);
+        let fractal = self.target.fractal();
+        let daily = set.all_iter().find(|d| *d.fractal() == fractal);
+
+        let mut ics = Ics::new(f);
+        ics.value("BEGIN", "VEVENT")?;
+        // the same day and target always get the same UID, so importing the
+        // file again updates the events instead of adding them twice
+        ics.value(
+            "UID",
+            format_args!("{}-{}@stab", Basic(self.date), Slug(self.target)),
+        )?;
+        // when the file was written, so a calendar taking it again knows the
+        // rating and instabilities may have changed since
+        ics.value("DTSTAMP", BasicTime(self.stamp))?;
+        ics.value("DTSTART;VALUE=DATE", Basic(self.date))?;
+        ics.value("DTEND;VALUE=DATE", Basic(self.date.successor()))?;
+        match daily {
+            Some(daily) => ics.text(
+                "SUMMARY",
+                format_args!(
+                    "{} ({}): {}",
+                    self.target,
+                    self.rating,
+                    WithAlt(daily.instabs(), daily.alt())
+                ),
+            )?,
+            None => ics.text("SUMMARY", format_args!("{} ({})", self.target, self.rating))?,
+        }
+        ics.value("TRANSP", "TRANSPARENT")?;
+        ics.value("END", "VEVENT")
+    }
+}
+
+/// The whole calendar `ics` writes: an event for each target on each of
+/// `Opts::ics_days` that `rater` rates well enough, whether or not the other
+/// targets pass that day.
+pub struct IcsCalendar<'a, F> {
+    opts: &'a Opts,
+    rater: F,
+    stamp: DateTime,
+}
+
+impl<'a, F> IcsCalendar<'a, F> {
+    pub const fn new(opts: &'a Opts, rater: F, stamp: DateTime) -> Self {
+        Self { opts, rater, stamp }
+    }
+}
+
+impl<F, U> Display for IcsCalendar<'_, F>
+where
+    F: Fn(Target) -> U,
+    U: Rater,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "{}", IcsHeader)?;
+        for date in self.opts.ics_days() {
+            for &target in self.opts.targets.iter() {
+                let rater = (self.rater)(target);
+                if let Some(rating) = self.opts.rate_target(date, &target, &rater) {
+                    write!(f, "{}", IcsEvent::new(date, target, rating, self.stamp))?;
+                }
+            }
+        }
+        write!(f, "{}", IcsFooter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::Plain;
+    use crate::{config::Defaults, Boss};
+
+    #[test]
+    fn event_lines() {
+        let date = Date::from_ymd(2026, 10, 19);
+        let stamp = DateTime {
+            date: Date::from_ymd(2026, 10, 18),
+            seconds: 3723,
+        };
+        let event = IcsEvent::new(date, Target::Boss(Boss::Mama), Rating::Bad, stamp);
+        let mut buf = [0u8; 1024];
+        let mut out = Plain(&mut buf, 0);
+        write!(out, "{}", event).unwrap();
+        let len = out.1;
+        let ics = core::str::from_utf8(&buf[..len]).unwrap();
+
+        assert!(ics.starts_with("BEGIN:VEVENT\r\nUID:20261019-mama@stab\r\n"));
+        assert!(ics.contains("DTSTAMP:20261018T010203Z\r\n"));
+        assert!(ics.contains("DTEND;VALUE=DATE:20261020\r\n"));
+        assert!(ics.contains("SUMMARY:Mama (Bad): No Pain\\, No Gain\\; "));
+        assert!(ics.ends_with("END:VEVENT\r\n"));
+    }
+
+    /// The calendar `stab` writes for `args`, leaving the user's config and
+    /// environment out of it.
+    fn calendar<'a>(args: &[&'static [u8]], buf: &'a mut [u8]) -> &'a str {
+        let mut argv = [core::ptr::null(); 16];
+        argv[0] = b"stab\0".as_ptr();
+        for (ptr, arg) in argv[1..].iter_mut().zip(args) {
+            *ptr = arg.as_ptr();
+        }
+        let opts = Opts::parse_with(args.len() as isize + 1, argv.as_ptr(), || {
+            Ok(Defaults::new())
+        })
+        .unwrap();
+
+        let stamp = DateTime {
+            date: Date::from_ymd(2026, 10, 18),
+            seconds: 0,
+        };
+        let mut out = Plain(buf, 0);
+        write!(out, "{}", IcsCalendar::new(&opts, |t| t, stamp)).unwrap();
+        let len = out.1;
+        core::str::from_utf8(&buf[..len]).unwrap()
+    }
+
+    #[test]
+    fn good_cm_days() {
+        // the example of the docs, from a fixed day
+        let mut buf = [0u8; 8192];
+        let ics = calendar(
+            &[
+                b"ics\0",
+                b"-b\0",
+                b"arkk\0",
+                b"-b\0",
+                b"ai\0",
+                b"-t\0",
+                b"good\0",
+                b"--days\0",
+                b"60\0",
+                b"--from\0",
+                b"2021-03-01\0",
+            ],
+            &mut buf,
+        );
+
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+        assert_eq!(ics.matches("BEGIN:VEVENT").count(), 16);
+        // each boss passes on its own, so Ai has an event on a day Arkk
+        // is too hard
+        assert!(ics.contains("UID:20210301-ai@stab\r\n"));
+        assert!(!ics.contains("UID:20210301-arkk@stab\r\n"));
+        assert!(ics.contains("UID:20210309-arkk@stab\r\n"));
+        assert!(ics.contains("UID:20210309-ai@stab\r\n"));
+        assert!(!ics.contains("(Playable)"));
+        assert_eq!(ics.matches("DTSTAMP:20261018T000000Z\r\n").count(), 16);
+    }
+
+    #[test]
+    fn all_bosses_by_default() {
+        let mut buf = [0u8; 32768];
+        let ics = calendar(
+            &[b"ics\0", b"--days\0", b"7\0", b"--from\0", b"2021-03-01\0"],
+            &mut buf,
+        );
+        assert_eq!(ics.matches("BEGIN:VEVENT").count(), 7 * 7);
+    }
+
+    #[test]
+    fn folding() {
+        let mut buf = [0u8; 256];
+        let mut out = Plain(&mut buf, 0);
+        Ics::new(&mut out)
+            .text("SUMMARY", format_args!("{:100}", "a"))
+            .unwrap();
+        let len = out.1;
+        let ics = core::str::from_utf8(&buf[..len]).unwrap();
+
+        assert_eq!(ics.matches("\r\n ").count(), 1);
+        assert!(ics.split("\r\n").all(|line| line.len() <= LINE_LEN));
+    }
+}
@@ -0,0 +1,207 @@
+use crate::{
+    fractal::FRACTAL_COUNT,
+    instab::INSTAB_COUNT,
+    set::{DailyOrCm, Instabs},
+    Boss, Date, Fractal, Rating, Set,
+};
+
+const DAY_COUNT: usize = 366;
+const WORDS: usize = DAY_COUNT.div_ceil(64);
+
+/// A set of days of the schedule, one bit for each of `PARSED_DATA`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Days([u64; WORDS]);
+
+impl Days {
+    pub const EMPTY: Self = Self([0; WORDS]);
+    pub const ALL: Self = Self::EMPTY.not();
+
+    const fn with(mut self, day: usize) -> Self {
+        self.0[day / 64] |= 1 << (day % 64);
+        self
+    }
+
+    pub const fn contains(&self, date: Date) -> bool {
+        let day = date.day() as usize;
+        self.0[day / 64] & 1 << (day % 64) != 0
+    }
+
+    pub const fn and(mut self, other: Self) -> Self {
+        let mut i = 0;
+        while i < WORDS {
+            self.0[i] &= other.0[i];
+            i += 1;
+        }
+        self
+    }
+
+    pub const fn or(mut self, other: Self) -> Self {
+        let mut i = 0;
+        while i < WORDS {
+            self.0[i] |= other.0[i];
+            i += 1;
+        }
+        self
+    }
+
+    pub const fn and_not(self, other: Self) -> Self {
+        self.and(other.not())
+    }
+
+    pub const fn not(mut self) -> Self {
+        let mut i = 0;
+        while i < WORDS {
+            self.0[i] = !self.0[i];
+            i += 1;
+        }
+        // keep the bits past the last day clear
+        self.0[WORDS - 1] &= (1 << (DAY_COUNT % 64)) - 1;
+        self
+    }
+
+    pub const fn count(&self) -> u32 {
+        let mut count = 0;
+        let mut i = 0;
+        while i < WORDS {
+            count += self.0[i].count_ones();
+            i += 1;
+        }
+        count
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.count() == 0
+    }
+}
+
+/// For every fractal and instability, the days it shows up on. Alternate
+/// instabilities count as showing up.
+pub(crate) type InstabIndex = [[Days; INSTAB_COUNT as usize]; FRACTAL_COUNT];
+
+/// For every boss and rating, the days its CM rates at least that well
+/// with the boss's own rater.
+pub(crate) type RatingIndex = [[Days; Rating::ALL.len()]; Boss::ALL.len()];
+
+const fn add_instabs(index: &mut InstabIndex, fractal: Fractal, instabs: &Instabs, day: usize) {
+    let raw = instabs.raw();
+    let mut i = 0;
+    while i < raw.len() {
+        let entry = &mut index[fractal as usize][raw[i] as usize];
+        *entry = entry.with(day);
+        i += 1;
+    }
+}
+
+pub(crate) const fn instab_index(data: &[Set; DAY_COUNT]) -> InstabIndex {
+    let mut index = [[Days::EMPTY; INSTAB_COUNT as usize]; FRACTAL_COUNT];
+
+    let mut day = 0;
+    while day < DAY_COUNT {
+        let set = &data[day];
+        add_instabs(&mut index, Fractal::Nightmare, &set.nightmare().0, day);
+        add_instabs(
+            &mut index,
+            Fractal::ShatteredObservatory,
+            &set.shattered_observatory().0,
+            day,
+        );
+        add_instabs(&mut index, Fractal::SunquaPeak, &set.sunqua_peak().0, day);
+
+        let dailies = set.dailies();
+        let mut i = 0;
+        while i < dailies.len() {
+            if let DailyOrCm::Daily(daily) = &dailies[i] {
+                let (instabs, alt) = daily.instab_sets();
+                add_instabs(&mut index, *daily.fractal(), instabs, day);
+                if let Some(alt) = alt {
+                    add_instabs(&mut index, *daily.fractal(), alt, day);
+                }
+            }
+            i += 1;
+        }
+        day += 1;
+    }
+
+    index
+}
+
+pub(crate) const fn rating_index(data: &[Set; DAY_COUNT]) -> RatingIndex {
+    let mut index = [[Days::EMPTY; Rating::ALL.len()]; Boss::ALL.len()];
+
+    let mut day = 0;
+    while day < DAY_COUNT {
+        let set = &data[day];
+        let mut b = 0;
+        while b < Boss::ALL.len() {
+            let boss = Boss::ALL[b];
+            let instabs = match boss.home() {
+                Fractal::Nightmare => &set.nightmare().0,
+                Fractal::ShatteredObservatory => &set.shattered_observatory().0,
+                _ => &set.sunqua_peak().0,
+            };
+
+            // same as `Rateable::rate`
+            let raw = instabs.raw();
+            let mut rating = Rating::Good;
+            let mut i = 0;
+            while i < raw.len() {
+                rating = rating.combine(boss.rate_instab(&raw[i]));
+                i += 1;
+            }
+
+            let mut r = 0;
+            while r <= rating as usize {
+                index[boss as usize][r] = index[boss as usize][r].with(day);
+                r += 1;
+            }
+            b += 1;
+        }
+        day += 1;
+    }
+
+    index
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{date::dataset_days, Instability, Parsable, Rateable, Scope, Searchable};
+
+    #[test]
+    fn bit_ops() {
+        let first = Date::from_ymd(2020, 1, 1);
+        let last = Date::from_ymd(2020, 12, 31);
+        let days = Days::EMPTY.with(0).with(365);
+
+        assert!(days.contains(first) && days.contains(last));
+        assert_eq!(Days::ALL.count(), 366);
+        assert_eq!(days.not().count(), 364);
+        assert_eq!(Days::ALL.and_not(days).or(days), Days::ALL);
+        assert!(days.and(days.not()).is_empty());
+    }
+
+    #[test]
+    fn index_matches_scan() {
+        for date in dataset_days() {
+            let set = Set::parse(date);
+            for fractal in Fractal::all() {
+                let scope = Scope::Fractal(fractal);
+                for instab in (1..=INSTAB_COUNT).filter_map(Instability::from_u8) {
+                    assert_eq!(
+                        Set::days_with(&instab, &scope).contains(date),
+                        set.has_instab(&instab, &scope),
+                    );
+                }
+            }
+            for boss in &Boss::ALL {
+                let cm = set.get(&set.find_fractal(&boss.home()).unwrap()).unwrap();
+                for &rating in &Rating::ALL {
+                    assert_eq!(
+                        Set::days_rated(boss, rating).contains(date),
+                        cm.rate(boss) >= rating,
+                    );
+                }
+            }
+        }
+    }
+}
@@ -1,3 +1,5 @@
+pub(crate) const INSTAB_COUNT: u8 = 18;
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum Instability {
     AdrenalineRush,
@@ -21,6 +23,60 @@ pub enum Instability {
 }
 
 impl Instability {
+    /// The main name `from_str` accepts for each instability, in order.
+    /// Shorter aliases, full names and unique prefixes are accepted as well.
+    pub const KEYWORDS: [&'static str; INSTAB_COUNT as usize] = [
+        "adrenaline-rush",
+        "afflicted",
+        "boon-overload",
+        "flux-bomb",
+        "fractal-vindicators",
+        "frailty",
+        "hamstrung",
+        "last-laugh",
+        "mists-convergence",
+        "no-pain-no-gain",
+        "outflanked",
+        "social-awkwardness",
+        "stick-together",
+        "sugar-rush",
+        "toxic-trail",
+        "vengeance",
+        "we-bleed-fire",
+        "toxic-sickness",
+    ];
+
+    /// The keywords and the aliases players use.
+    fn keyword(s: &str) -> Option<Self> {
+        use Instability::*;
+
+        Some(match s {
+            "adrenaline-rush" | "adrenaline" => AdrenalineRush,
+            "afflicted" => Afflicted,
+            "boon-overload" | "boon" | "boons" => BoonOverload,
+            "flux-bomb" | "flux" => FluxBomb,
+            "fractal-vindicators" | "vindicators" | "vindis" => FractalVindicators,
+            "frailty" => Frailty,
+            "hamstrung" => Hamstrung,
+            "last-laugh" | "laugh" | "ll" => LastLaugh,
+            "mists-convergence" | "mists" | "convergence" => MistsConvergence,
+            "no-pain-no-gain" | "no-pain" | "npng" => NoPainNoGain,
+            "outflanked" => Outflanked,
+            "social-awkwardness" | "social" | "awkwardness" => SocialAwkwardness,
+            "stick-together" | "stick" => StickTogether,
+            "sugar-rush" | "sugar" => SugarRush,
+            "toxic-trail" | "trail" => ToxicTrail,
+            "vengeance" => Vengeance,
+            "we-bleed-fire" | "bleed" | "wbf" => WeBleedFire,
+            "toxic-sickness" | "sickness" => ToxicSickness,
+            _ => return None,
+        })
+    }
+
+    pub fn all() -> impl Iterator<Item = Instability> + Clone {
+        (1..=INSTAB_COUNT).filter_map(Instability::from_u8)
+    }
+
     pub const fn from_u8(n: u8) -> Option<Self> {
         use Instability::*;
         match n {
@@ -47,6 +103,14 @@ impl Instability {
     }
 }
 
+impl core::str::FromStr for Instability {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        crate::keyword::lookup(s, Self::all(), &Self::KEYWORDS, Self::keyword)
+    }
+}
+
 impl core::fmt::Display for Instability {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         use Instability::*;
@@ -72,6 +136,6 @@ impl core::fmt::Display for Instability {
             ToxicSickness => "Toxic Sickness",
         };
 
-        write!(f, "{}", name)
+        f.pad(name)
     }
 }
@@ -0,0 +1,160 @@
+use core::fmt::{Display, Result, Write};
+
+/// Writes JSON to `out` as it goes, keeping track of commas and of which
+/// arrays and objects are open. Up to 32 of them can be nested.
+pub struct Json<'a, W: ?Sized> {
+    out: &'a mut W,
+    depth: u32,
+    /// Per level, whether it already has a member.
+    filled: u32,
+    /// Per level, whether it is an array rather than an object.
+    arrays: u32,
+    /// Whether a key was just written, so the value needs no comma.
+    after_key: bool,
+}
+
+impl<'a, W> Json<'a, W>
+where
+    W: Write + ?Sized,
+{
+    pub fn new(out: &'a mut W) -> Self {
+        Self {
+            out,
+            depth: 0,
+            filled: 0,
+            arrays: 0,
+            after_key: false,
+        }
+    }
+
+    /// Writes a comma if the current array or object needs one.
+    fn separate(&mut self) -> Result {
+        if self.after_key {
+            self.after_key = false;
+            return Ok(());
+        }
+        if self.depth > 0 {
+            let bit = 1 << (self.depth - 1);
+            if self.filled & bit != 0 {
+                self.out.write_char(',')?;
+            }
+            self.filled |= bit;
+        }
+        Ok(())
+    }
+
+    fn open(&mut self, array: bool) -> Result {
+        self.separate()?;
+        let bit = 1 << self.depth;
+        self.filled &= !bit;
+        if array {
+            self.arrays |= bit;
+        } else {
+            self.arrays &= !bit;
+        }
+        self.depth += 1;
+        self.out.write_char(if array { '[' } else { '{' })
+    }
+
+    pub fn object(&mut self) -> Result {
+        self.open(false)
+    }
+
+    pub fn array(&mut self) -> Result {
+        self.open(true)
+    }
+
+    /// Closes the innermost array or object.
+    pub fn end(&mut self) -> Result {
+        self.depth -= 1;
+        let array = self.arrays & 1 << self.depth != 0;
+        self.out.write_char(if array { ']' } else { '}' })
+    }
+
+    pub fn key(&mut self, key: impl Display) -> Result {
+        self.string(key)?;
+        self.after_key = true;
+        self.out.write_char(':')
+    }
+
+    /// Writes what `value` displays as, as a string.
+    pub fn string(&mut self, value: impl Display) -> Result {
+        self.separate()?;
+        self.out.write_char('"')?;
+        write!(Escaped(&mut *self.out), "{}", value)?;
+        self.out.write_char('"')
+    }
+
+    pub fn number(&mut self, value: u32) -> Result {
+        self.separate()?;
+        write!(self.out, "{}", value)
+    }
+
+    pub fn boolean(&mut self, value: bool) -> Result {
+        self.separate()?;
+        write!(self.out, "{}", value)
+    }
+
+    pub fn null(&mut self) -> Result {
+        self.separate()?;
+        self.out.write_str("null")
+    }
+
+    /// A key with a string value.
+    pub fn field(&mut self, key: impl Display, value: impl Display) -> Result {
+        self.key(key)?;
+        self.string(value)
+    }
+}
+
+/// Escapes what is written for use inside a JSON string.
+struct Escaped<'a, W: ?Sized>(&'a mut W);
+
+impl<W> Write for Escaped<'_, W>
+where
+    W: Write + ?Sized,
+{
+    fn write_str(&mut self, s: &str) -> Result {
+        for c in s.chars() {
+            match c {
+                '"' => self.0.write_str("\\\"")?,
+                '\\' => self.0.write_str("\\\\")?,
+                '\n' => self.0.write_str("\\n")?,
+                c if (c as u32) < 0x20 => write!(self.0, "\\u{:04x}", c as u32)?,
+                c => self.0.write_char(c)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::Plain;
+
+    #[test]
+    fn nesting_and_escapes() {
+        let mut buf = [0u8; 128];
+        let mut out = Plain(&mut buf, 0);
+        let mut json = Json::new(&mut out);
+        json.object().unwrap();
+        json.field("name", "say \"hi\"\n").unwrap();
+        json.key("list").unwrap();
+        json.array().unwrap();
+        json.number(1).unwrap();
+        json.object().unwrap();
+        json.end().unwrap();
+        json.null().unwrap();
+        json.end().unwrap();
+        json.key("ok").unwrap();
+        json.boolean(true).unwrap();
+        json.end().unwrap();
+        let len = out.1;
+
+        assert_eq!(
+            core::str::from_utf8(&buf[..len]).unwrap(),
+            r#"{"name":"say \"hi\"\n","list":[1,{},null],"ok":true}"#
+        );
+    }
+}
@@ -0,0 +1,177 @@
+use core::fmt::{Display, Write};
+
+/// Longest input worth looking up or suggesting a keyword for.
+const MAX_LEN: usize = 32;
+
+/// Finds what `s` names: an exact keyword or alias as `keyword` knows it,
+/// then a full `Display` name, then a prefix of either that only one value
+/// has. Case doesn't matter, and `-`, `_` and spaces are all alike.
+pub(crate) fn lookup<T>(
+    s: &str,
+    all: impl Iterator<Item = T> + Clone,
+    keywords: &[&str],
+    keyword: impl Fn(&str) -> Option<T>,
+) -> Result<T, ()>
+where
+    T: Copy + PartialEq + Display,
+{
+    if let Some(found) = exact(s, all.clone(), &keyword) {
+        return Ok(found);
+    }
+    if s.is_empty() || s.len() > MAX_LEN {
+        return Err(());
+    }
+
+    let mut found = None;
+    let prefixes = keywords
+        .iter()
+        .filter(|k| compare(s, k).is_some())
+        .filter_map(|k| keyword(k))
+        .chain(all.filter(|v| compare(s, v).is_some()));
+    for value in prefixes {
+        match found {
+            Some(f) if f != value => return Err(()),
+            _ => found = Some(value),
+        }
+    }
+    found.ok_or(())
+}
+
+/// Like `lookup`, but only what `s` names in full: a keyword, an alias or
+/// a `Display` name.
+pub(crate) fn exact<T>(
+    s: &str,
+    mut all: impl Iterator<Item = T>,
+    keyword: impl Fn(&str) -> Option<T>,
+) -> Option<T>
+where
+    T: Display,
+{
+    if s.is_empty() || s.len() > MAX_LEN {
+        return None;
+    }
+
+    let mut buf = [0u8; MAX_LEN];
+    let lower = &mut buf[..s.len()];
+    lower.copy_from_slice(s.as_bytes());
+    lower.make_ascii_lowercase();
+    core::str::from_utf8(lower)
+        .ok()
+        .and_then(keyword)
+        .or_else(|| all.find(|v| compare(s, v) == Some(true)))
+}
+
+/// The keywords `s` is a prefix of, more than one meaning `lookup` found it
+/// ambiguous.
+pub(crate) fn prefixed<'a>(
+    s: &'a str,
+    keywords: &'a [&'static str],
+) -> impl Iterator<Item = &'static str> + Clone + 'a {
+    keywords
+        .iter()
+        .copied()
+        .filter(move |k| !s.is_empty() && compare(s, k).is_some())
+}
+
+/// The keyword closest to `s`, if it's close enough to be a typo.
+pub(crate) fn suggest(s: &str, keywords: &[&'static str]) -> Option<&'static str> {
+    keywords
+        .iter()
+        .filter_map(|&k| distance(s, k).map(|d| (d, k)))
+        .filter(|&(d, k)| d <= 2 && d < k.len())
+        .min_by_key(|&(d, _)| d)
+        .map(|(_, k)| k)
+}
+
+const fn normalize(b: u8) -> u8 {
+    match b {
+        b'-' | b'_' => b' ',
+        _ => b.to_ascii_lowercase(),
+    }
+}
+
+/// Whether `prefix` starts what `value` displays as: `Some(true)` when it's
+/// all of it, `None` when it isn't a prefix at all.
+fn compare(prefix: &str, value: impl Display) -> Option<bool> {
+    struct Prefix<'a> {
+        rest: &'a [u8],
+        matches: bool,
+        longer: bool,
+    }
+
+    impl Write for Prefix<'_> {
+        fn write_str(&mut self, s: &str) -> core::fmt::Result {
+            for &b in s.as_bytes() {
+                match self.rest.split_first() {
+                    None => self.longer = true,
+                    Some((&p, rest)) => {
+                        self.matches &= normalize(p) == normalize(b);
+                        self.rest = rest;
+                    }
+                }
+            }
+            Ok(())
+        }
+    }
+
+    let mut out = Prefix {
+        rest: prefix.as_bytes(),
+        matches: true,
+        longer: false,
+    };
+    write!(out, "{}", value).ok()?;
+    if out.matches && out.rest.is_empty() {
+        Some(!out.longer)
+    } else {
+        None
+    }
+}
+
+/// Edit distance counting swapped neighbours as one edit.
+fn distance(a: &str, b: &str) -> Option<usize> {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    if a.len() > MAX_LEN || b.len() > MAX_LEN {
+        return None;
+    }
+
+    // rows for the two previous prefixes of `a` and the current one
+    let mut rows = [[0usize; MAX_LEN + 1]; 3];
+    for (j, d) in rows[0].iter_mut().enumerate() {
+        *d = j;
+    }
+    for i in 1..=a.len() {
+        let (two, one, cur) = ((i + 1) % 3, (i + 2) % 3, i % 3);
+        rows[cur][0] = i;
+        for j in 1..=b.len() {
+            let same = normalize(a[i - 1]) == normalize(b[j - 1]);
+            let mut d = (rows[one][j] + 1)
+                .min(rows[cur][j - 1] + 1)
+                .min(rows[one][j - 1] + !same as usize);
+            if i > 1
+                && j > 1
+                && normalize(a[i - 1]) == normalize(b[j - 2])
+                && normalize(a[i - 2]) == normalize(b[j - 1])
+            {
+                d = d.min(rows[two][j - 2] + 1);
+            }
+            rows[cur][j] = d;
+        }
+    }
+    Some(rows[a.len() % 3][b.len()])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn distances() {
+        assert_eq!(distance("arkk", "arkk"), Some(0));
+        assert_eq!(distance("ARKK", "arkk"), Some(0));
+        assert_eq!(distance("akrk", "arkk"), Some(1));
+        assert_eq!(distance("siaxx", "siax"), Some(1));
+        assert_eq!(distance("", "ai"), Some(2));
+        assert_eq!(suggest("skrov", &["mama", "skorv"]), Some("skorv"));
+        assert_eq!(suggest("xyz", &["mama", "skorv"]), None);
+    }
+}
@@ -2,19 +2,40 @@
 #![feature(const_mut_refs)]
 #![feature(const_option)]
 
+pub mod cal;
 pub mod color;
+pub mod complete;
+pub mod config;
+pub mod csv;
 pub mod date;
+pub mod format;
 pub mod fractal;
+pub mod fs;
+pub mod ics;
+pub mod index;
 pub mod instab;
+pub mod json;
+mod keyword;
 pub mod opt;
 pub mod parse;
+pub mod query;
 pub mod rating;
 pub mod set;
+pub mod stats;
+pub mod team;
+#[cfg(test)]
+mod test_util;
 
 pub use date::Date;
 pub use fractal::Fractal;
+pub use index::Days;
 pub use instab::Instability;
 pub use opt::Opts;
 pub use parse::Parsable;
-pub use rating::{Boss, Rateable, Rater, Rating};
-pub use set::{HasInstabs, Searchable, Set};
+pub use query::Query;
+pub use rating::{
+    AltPolicy, AltRateable, Boss, DayRating, Rateable, Rater, Rating, Role, RoleRater, Target,
+};
+pub use set::{HasInstabs, InstabFilter, Observation, Scope, Searchable, Set};
+pub use stats::Stats;
+pub use team::{Consensus, Policy, Team};
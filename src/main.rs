@@ -2,10 +2,18 @@
 #![no_main]
 #![windows_subsystem = "console"]
 
-use libc_print::std_name::{print, println};
+use libc_print::std_name::{eprintln, print, println};
+use stab::date::{future_days_where, ranked_days, year_ahead, DateTime, Run};
 
 use stab::{
-    color::ColorByRater, Boss, Fractal, Opts, Parsable, Rateable, Rater, Rating, Searchable, Set,
+    cal::{Calendar, Heatmap},
+    color::{ColorByRater, DailyColored, Theme},
+    complete::Completions,
+    format::{CsvDay, CsvHeader, DiscordDay, Format, JsonDay, MarkdownDay, Messages},
+    ics::IcsCalendar,
+    opt::{Command, EXIT_FAILURE, EXIT_USAGE, USAGE},
+    AltRateable, Consensus, Date, DayRating, Opts, Parsable, Query, Rater, Rating, RoleRater,
+    Scope, Searchable, Set, Stats, Target,
 };
 
 #[cfg(not(test))]
@@ -13,7 +21,7 @@ use stab::{
 fn panic(_info: &core::panic::PanicInfo) -> ! {
     #[cfg(any(windows, debug_assertions))]
     libc_print::libc_eprintln!("{}", _info);
-    unsafe { libc::exit(libc::EXIT_FAILURE) }
+    unsafe { libc::exit(EXIT_FAILURE as libc::c_int) }
 }
 
 #[cfg(windows)]
@@ -29,60 +37,407 @@ mod windows {
 
 #[no_mangle]
 pub extern "C" fn main(argc: isize, argv: *const *const u8) -> isize {
-    let opts = Opts::parse(argc, argv);
+    let opts = match Opts::parse(argc, argv) {
+        Ok(opts) => opts,
+        Err(e) => {
+            eprintln!("stab: {}", e);
+            if e.exit_code() == EXIT_USAGE {
+                eprintln!("Try 'stab --help' for more information.");
+            }
+            return e.exit_code();
+        }
+    };
+    stab::color::set_theme(match opts.theme {
+        // Discord only draws the basic colours
+        Theme::Default | Theme::Bright if opts.format == Format::Discord => Theme::Basic,
+        theme => theme,
+    });
+    stab::date::set_utc_offset(opts.utc_offset);
 
-    if let Some(boss) = opts.boss {
-        run_for_boss(boss, opts.num, opts.threshold);
-        return 0;
-    } else if let Some(frac) = opts.fractal {
-        run_for_fractal(frac, opts.num, opts.threshold);
-        return 0;
+    if let Some(separator) = opts.format.separator() {
+        println!("{}", CsvHeader::new(separator));
     }
 
-    use stab::color::SetColored;
-
-    for (i, date, set) in stab::date::future_days()
-        .take(opts.num as usize)
-        .enumerate()
-        .map(|(i, d)| (i, d, Set::parse(d)))
-    {
-        println!("{}", date);
-        print!("{}", set.colored());
-        if i < opts.num as usize - 1 {
-            println!();
+    // how full the current message is, for --format discord
+    let messages = Messages::new();
+    match opts.command {
+        Command::Help => {
+            print!("{}", USAGE);
         }
+        Command::Version => {
+            println!("stab {}", env!("CARGO_PKG_VERSION"));
+        }
+        Command::Stats => run_stats(&opts),
+        Command::Whereis => run_whereis(&opts, &messages),
+        Command::Find => {
+            let query = opts.query.unwrap();
+            run_query(query.with_alt_policy(opts.alt_policy), &opts, &messages);
+        }
+        Command::Export => run_export(&opts, &messages),
+        Command::Validate => return run_validate(),
+        Command::Completions => {
+            print!("{}", Completions::new(opts.shell.unwrap()));
+        }
+        _ if !opts.targets.is_empty() => run_with(&opts, &messages),
+        _ => run_days(&opts, &messages),
     }
 
+    if opts.format == Format::Discord {
+        print!("{}", messages.end());
+    }
     0
 }
 
-fn run<U>(fractal: Fractal, n: u16, threshold: Rating, comp: &U)
+fn run_days(opts: &Opts, messages: &Messages) {
+    use stab::color::{ColorRating, DefaultColors, SetColored};
+
+    let instab_days = opts.instabs.days(Scope::Any);
+    let key = |d| {
+        if !instab_days.contains(d) {
+            return None;
+        }
+        let day = Set::parse(d).rate_day(opts.alt_policy);
+        if day.rating >= opts.threshold {
+            Some(day)
+        } else {
+            None
+        }
+    };
+
+    if run_keyed(opts, key) {
+        return;
+    }
+    let print_day = |i, date| {
+        if print_record(opts, messages, date, core::iter::empty()) {
+            return;
+        }
+        if i > 0 {
+            println!();
+        }
+        let set = Set::parse(date);
+        let rating = set.rate_day(opts.alt_policy).rating;
+        println!(
+            "{}",
+            ColorRating::<DefaultColors>::color_by_rating(&date, rating)
+        );
+        print!("{}", set.colored());
+    };
+
+    if opts.best {
+        for (i, (date, _)) in ranked_days(opts.window as usize, key)
+            .take(opts.num as usize)
+            .enumerate()
+        {
+            print_day(i, date);
+        }
+    } else {
+        for (i, date) in future_days_where(|d| key(d).is_some())
+            .take(opts.num as usize)
+            .enumerate()
+        {
+            print_day(i, date);
+        }
+    }
+}
+
+fn run<U, F>(opts: &Opts, messages: &Messages, rater: F)
 where
     U: Rater + Copy,
+    F: Fn(Target) -> U,
+{
+    use stab::color::{ColorRating, DefaultColors};
+
+    let find = |date, target: &Target| {
+        let set = Set::parse(date);
+        set.find_fractal(&target.fractal())
+            .and_then(|r| set.get(&r))
+    };
+    let key = |date| {
+        opts.targets.combine(opts.match_all, |t| {
+            let rater = rater(*t);
+            let rating = opts.rate_target(date, t, &rater)?;
+            let day = find(date, t).unwrap().day_rating(&rater, opts.alt_policy);
+            Some(DayRating { rating, ..day })
+        })
+    };
+
+    if opts.command == Command::Ics {
+        print!("{}", IcsCalendar::new(opts, &rater, DateTime::now()));
+        return;
+    }
+    if run_keyed(opts, key) {
+        return;
+    }
+    let print_day = |date| {
+        let ratings = opts.targets.iter().filter_map(|&target| {
+            let daily = find(date, &target)?;
+            Some((target, daily.rate_with_alt(&rater(target), opts.alt_policy)))
+        });
+        if print_record(opts, messages, date, ratings) {
+            return;
+        }
+        if opts.targets.len() == 1 {
+            let target = opts.targets.iter().next().unwrap();
+            let daily = find(date, target).unwrap();
+            // let rating = daily.rate(comp);
+            // println!("{} {}", date.color_by_rating(rating), daily.instabs());
+            println!(
+                "{} {}",
+                date,
+                daily
+                    .color_by_rater(rater(*target))
+                    .with_alt_policy(opts.alt_policy)
+            );
+            // println!(
+            //     "{} {}: {}",
+            //     date,
+            //     daily.fractal(), /*.color_by_rating(rating)*/
+            //     daily.instabs()
+            // );
+            return;
+        }
+
+        print!("{}", date);
+        for target in opts.targets.iter() {
+            if let Some(daily) = find(date, target) {
+                let rating = daily.rate_with_alt(&rater(*target), opts.alt_policy);
+                print!(
+                    "  {}: {}",
+                    ColorRating::<DefaultColors>::color_by_rating(target, rating),
+                    rating
+                );
+            }
+        }
+        println!();
+
+        for (i, target) in opts.targets.iter().enumerate() {
+            let mut seen = opts.targets.iter().take(i);
+            if let Some(daily) = find(date, target) {
+                if !seen.any(|t| t.fractal() == target.fractal()) {
+                    println!(
+                        "  {}",
+                        daily
+                            .color_by_rater(rater(*target))
+                            .with_alt_policy(opts.alt_policy)
+                    );
+                }
+            }
+        }
+    };
+
+    if opts.command == Command::Rate {
+        let date = opts.date.unwrap_or_else(Date::today);
+        print_day(date);
+        if opts.format != Format::Text {
+            return;
+        }
+        if let Some(day) = key(date) {
+            println!(
+                "overall: {}, score {}",
+                ColorRating::<DefaultColors>::color_by_rating(&day.rating, day.rating),
+                day.score
+            );
+        }
+    } else if opts.best {
+        for (date, _) in ranked_days(opts.window as usize, key).take(opts.num as usize) {
+            print_day(date);
+        }
+    } else {
+        for date in future_days_where(|d| key(d).is_some()).take(opts.num as usize) {
+            print_day(date);
+        }
+    }
+}
+
+/// Prints `date` as a record when `--format` asks for one, with the rating
+/// by each target for formats that have room for them.
+fn print_record<I>(opts: &Opts, messages: &Messages, date: Date, ratings: I) -> bool
+where
+    I: Iterator<Item = (Target, Rating)> + Clone,
 {
-    for (date, set, daily) in stab::date::future_days()
-        .map(|d| (d, Set::parse(d)))
-        .filter_map(|(date, s)| s.find_fractal(&fractal).map(|d| (date, s, d)))
-        .filter(|(_, s, r)| s.get(r).unwrap().rate(comp) >= threshold)
-        .take(n as usize)
+    match opts.format {
+        Format::Text => return false,
+        Format::Json => {
+            println!("{}", JsonDay::new(date, opts.alt_policy, ratings))
+        }
+        Format::Csv | Format::Tsv => {
+            let separator = opts.format.separator().unwrap();
+            println!("{}", CsvDay::new(date, opts.alt_policy, separator))
+        }
+        Format::Markdown => {
+            println!("{}\n", MarkdownDay::new(date, opts.alt_policy, ratings))
+        }
+        Format::Discord => {
+            let day = DiscordDay::new(date, opts.alt_policy, ratings);
+            print!("{}", messages.record(day))
+        }
+    }
+    true
+}
+
+fn run_query(query: Query, opts: &Opts, messages: &Messages) {
+    for date in future_days_where(|d| query.matching(d, Set::parse(d)).next().is_some())
+        .take(opts.num as usize)
     {
-        let daily = set.get(&daily).unwrap();
-        // let rating = daily.rate(comp);
-        // println!("{} {}", date.color_by_rating(rating), daily.instabs());
-        println!("{} {}", date, daily.color_by_rater(*comp));
-        // println!(
-        //     "{} {}: {}",
-        //     date,
-        //     daily.fractal(), /*.color_by_rating(rating)*/
-        //     daily.instabs()
-        // );
+        if print_record(opts, messages, date, core::iter::empty()) {
+            continue;
+        }
+        for daily in query.matching(date, Set::parse(date)) {
+            match query.boss_for(*daily.fractal()) {
+                Some(boss) if boss.home() == *daily.fractal() => {
+                    println!("{} {}", date, daily.color_by_rater(boss));
+                }
+                _ => {
+                    println!("{} {}", date, daily.daily_colored());
+                }
+            }
+        }
+    }
+}
+
+/// Runs the commands that only need to know how each day rates, returning
+/// whether `opts.command` was one of them.
+fn run_keyed(opts: &Opts, key: impl Fn(Date) -> Option<DayRating>) -> bool {
+    match opts.command {
+        Command::Cal => print_calendar(opts, key),
+        Command::Heatmap => print_heatmap(opts, key),
+        Command::Streaks => print_streaks(opts, key),
+        _ => return false,
+    }
+    true
+}
+
+fn print_calendar(opts: &Opts, key: impl Fn(Date) -> Option<DayRating>) {
+    let (year, month) = opts.month.unwrap_or_else(|| {
+        let (year, month, _) = Date::today().ymd();
+        (year, month)
+    });
+    print!(
+        "{}",
+        Calendar::new(year, month, |d| key(d).map(|k| k.rating))
+    );
+}
+
+fn print_heatmap(opts: &Opts, key: impl Fn(Date) -> Option<DayRating>) {
+    let year = opts.year.unwrap_or_else(|| Date::today().year());
+    print!("{}", Heatmap::new(year, |d| key(d).map(|k| k.rating)));
+}
+
+fn run_whereis(opts: &Opts, messages: &Messages) {
+    use stab::date::schedule_days;
+
+    // `Opts::parse` makes sure there is something to look for
+    let observed = opts.observed.unwrap_or_default();
+    let mut found = false;
+    for date in schedule_days() {
+        let set = Set::parse(date);
+        if opts.format != Format::Text {
+            if observed.matching(set).next().is_some() {
+                print_record(opts, messages, date, core::iter::empty());
+            }
+            continue;
+        }
+        for daily in observed.matching(set) {
+            println!("{} {}", date, daily.daily_colored());
+            found = true;
+        }
+    }
+    if !found && opts.format == Format::Text {
+        println!("no day matches");
+    }
+}
+
+struct Span(Run);
+
+impl core::fmt::Display for Span {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let Run {
+            first, last, days, ..
+        } = self.0;
+        let unit = if days == 1 { "day" } else { "days" };
+        write!(f, "{} to {} {:>3} {}", first, last, days, unit)
+    }
+}
+
+fn print_streaks(opts: &Opts, key: impl Fn(Date) -> Option<DayRating>) {
+    use stab::date::{days_between, runs};
+
+    let (from, to) = opts.date_range().unwrap_or_else(year_ahead);
+    let mut longest: Option<Run> = None;
+    let mut drought: Option<Run> = None;
+    for run in runs(days_between(from, to), |d| key(d).is_some()) {
+        let best = if run.passed {
+            println!("{}", Span(run));
+            &mut longest
+        } else {
+            &mut drought
+        };
+        if best.is_none_or(|best| run.days > best.days) {
+            *best = Some(run);
+        }
+    }
+
+    if let Some(run) = longest {
+        println!("longest streak:  {}", Span(run));
+    } else {
+        println!("no day passes");
+    }
+    if let Some(run) = drought {
+        println!("longest drought: {}", Span(run));
+    }
+}
+
+fn run_export(opts: &Opts, messages: &Messages) {
+    use stab::date::days_between;
+
+    let (from, to) = opts.date_range().unwrap_or_else(year_ahead);
+    for date in days_between(from, to) {
+        if print_record(opts, messages, date, core::iter::empty()) {
+            continue;
+        }
+        for daily in Set::parse(date).all_iter() {
+            println!("{} {}", date, daily);
+        }
+    }
+}
+
+fn run_validate() -> isize {
+    use stab::date::dataset_days;
+
+    let mut problems = 0;
+    for date in dataset_days() {
+        if let Err(e) = Set::parse(date).check() {
+            let (month, day) = date.month_day();
+            println!("{:02}-{:02}: {}", month, day, e);
+            problems += 1;
+        }
+    }
+
+    if problems > 0 {
+        println!("{} days with problems", problems);
+        EXIT_FAILURE
+    } else {
+        println!("all {} days look fine", dataset_days().count());
+        0
     }
 }
 
-fn run_for_fractal(fractal: Fractal, n: u16, threshold: Rating) {
-    run::<Fractal>(fractal, n, threshold, &fractal)
+fn run_stats(opts: &Opts) {
+    use stab::date::{dataset_days, days_between};
+
+    let stats = match opts.date_range() {
+        Some((from, to)) => Stats::collect(days_between(from, to)),
+        None => Stats::collect(dataset_days()),
+    };
+    print!("{}", stats);
 }
 
-fn run_for_boss(boss: Boss, n: u16, threshold: Rating) {
-    run::<Boss>(boss.home(), n, threshold, &boss)
+fn run_with(opts: &Opts, messages: &Messages) {
+    if let Some(team) = opts.team {
+        run(opts, messages, |t| Consensus::new(t, team, opts.policy))
+    } else if let Some(role) = opts.role {
+        run(opts, messages, |t| RoleRater::new(t, role))
+    } else {
+        run(opts, messages, |t| t)
+    }
 }
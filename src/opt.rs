@@ -1,22 +1,534 @@
 use core::{marker::PhantomData, str::FromStr};
 
-use crate::{Boss, Fractal, Rating};
+use crate::{
+    color::Theme,
+    complete::Shell,
+    config::Defaults,
+    date::{parse_utc_offset, year_ahead},
+    format::Format,
+    fs::ReadError,
+    query::QueryError,
+    team::TeamError,
+    AltPolicy, AltRateable, Boss, Date, DayRating, Fractal, InstabFilter, Instability, Observation,
+    Parsable, Policy, Query, Rater, Rating, Role, Scope, Searchable, Set, Target, Team,
+};
+
+const MAX_TARGETS: usize = 8;
+/// Longest query `find` takes, once its arguments are joined.
+const MAX_QUERY: usize = 256;
+
+/// The bosses and fractals given with `-b` and `-f`, in order.
+#[derive(Debug, Copy, Clone)]
+pub struct Targets {
+    items: [Target; MAX_TARGETS],
+    len: usize,
+}
+
+impl Targets {
+    pub(crate) const fn new() -> Self {
+        Self {
+            items: [Target::Boss(Boss::Mama); MAX_TARGETS],
+            len: 0,
+        }
+    }
+
+    pub(crate) fn push(&mut self, target: Target) -> Result<(), OptError> {
+        if self.iter().any(|&t| t == target) {
+            return Ok(());
+        }
+        if self.len == MAX_TARGETS {
+            return Err(OptError::TooManyTargets);
+        }
+        self.items[self.len] = target;
+        self.len += 1;
+        Ok(())
+    }
+
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Target> + Clone {
+        self.items[..self.len].iter()
+    }
+
+    /// What the targets make of a day, given what `rate` makes of each of
+    /// them, `None` for one that doesn't pass. With `all` every target has
+    /// to pass, and the day gets the sum of the ratings, the worst winning
+    /// like for a whole day, and the average score. Otherwise it gets the
+    /// best rating of those that pass.
+    pub fn combine(
+        &self,
+        all: bool,
+        rate: impl Fn(&Target) -> Option<DayRating>,
+    ) -> Option<DayRating> {
+        let ratings = self.iter().map(rate);
+        if !all {
+            return ratings.flatten().max();
+        }
+
+        let mut rating = Rating::Good;
+        let (mut score, mut count) = (0u16, 0u16);
+        for day in ratings {
+            let day = day?;
+            rating = rating + day.rating;
+            score += day.score as u16;
+            count += 1;
+        }
+        Some(DayRating {
+            rating,
+            // no targets, no rating
+            score: score.checked_div(count)? as u8,
+        })
+    }
+}
+
+/// Exit status for a bad command line.
+pub const EXIT_USAGE: isize = 2;
+/// Exit status for anything that goes wrong after the command line was
+/// read.
+pub const EXIT_FAILURE: isize = 1;
+
+pub const USAGE: &str = "\
+usage: stab [command] [options]
+
+commands:
+  today                  today's fractals
+  next                   upcoming days passing the filters, the default
+  find QUERY...          upcoming fractals matching QUERY, which may be
+                         split over several arguments
+  cal [M|YYYY-MM]        month calendar coloured by rating
+  heatmap [YYYY]         a whole year coloured by rating, this year by
+                         default
+  streaks                runs of days passing the filters
+  stats                  instability and rating statistics
+  rate [DATE]            ratings of a single day
+  whereis [FRACTAL] INSTAB...
+                         days with the given instabilities
+  export                 the schedule as plain text
+  ics                    an iCalendar file with an event per target and day
+                         passing the filters, all bosses by default
+  validate               check the embedded schedule
+  completions SHELL      completion script for bash, zsh or fish
+
+rating options (today, next, cal, heatmap, streaks, rate, ics):
+  -b, --boss BOSS        rate by a CM boss, may be repeated
+  -f, --fractal FRACTAL  rate by a fractal, may be repeated
+  --all, --any           whether every or any -b/-f has to pass, not ics,
+                         which has an event for each one that does
+  --role ROLE            rate for a role
+  --team FILE            rate for the team in FILE
+  --policy POLICY        how a team's ratings are combined
+  --alt POLICY           how alternate instabilities are rated, also find
+
+filter options (next, cal, heatmap, streaks, ics):
+  -t, --threshold RATING only days rated at least RATING
+  --with INSTAB          only days with INSTAB
+  --without INSTAB       only days without INSTAB
+  --in SCOPE             where --with and --without look
+
+other options:
+  -n, --num NUM          number of days to show (next, find)
+  --best                 rank the days in the window instead (next)
+  --window DAYS          days --best looks at (next)
+  --from DATE, --to DATE range to look at (streaks, stats, export, ics)
+  --days DAYS            days from --from or today to look at (ics)
+  --format FORMAT        text, json with one object per day and line, csv
+                         or tsv with one row per day, markdown, or
+                         discord with ```ansi blocks in messages of at most
+                         2000 characters, separated by form feed lines
+                         (today, next, find, rate, whereis, export)
+  --theme THEME          default, bright, plain or basic colours
+  --timezone OFFSET      which day is today, like +02:00 or utc
+  -h, --help             show this help
+  -V, --version          show the version
+
+Values can also be given as --name=VALUE or -nVALUE, short flags without
+values can be bundled like -hV, and -- ends the options. Bosses, fractals
+and ratings may be given in any case, by their full names or by any prefix
+only one of them has.
+
+A team file has a member per line as 'name [role] [instability=rating...]',
+where the ratings override both the role and the boss or fractal. Each
+member rates a whole daily, and --policy combines those ratings.
+
+Defaults for boss, fractal, threshold, theme and timezone are read from
+$XDG_CONFIG_HOME/stab/config as lines like 'boss = arkk, ai', then from
+STAB_BOSS, STAB_FRACTAL, STAB_THRESHOLD, STAB_THEME and STAB_TIMEZONE.
+Options on the command line override both.
+";
+
+/// Describes what a flag accepts.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Expected {
+    Number,
+    Date,
+    Month,
+    Year,
+    Scope,
+    UtcOffset,
+    File,
+    OneOf(&'static [&'static str]),
+}
+
+impl Expected {
+    fn keywords(&self) -> &'static [&'static str] {
+        match self {
+            Expected::OneOf(keywords) => keywords,
+            Expected::Scope => &Fractal::KEYWORDS,
+            _ => &[],
+        }
+    }
+
+    /// A keyword `value` was probably meant to be.
+    fn suggest(&self, value: &str) -> Option<&'static str> {
+        crate::keyword::suggest(value, self.keywords())
+    }
+}
+
+impl core::fmt::Display for Expected {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let keywords = match self {
+            Expected::Number => return write!(f, "a number"),
+            Expected::Date => return write!(f, "a date like 2021-03-01"),
+            Expected::Month => return write!(f, "a month like 3 or 2021-03"),
+            Expected::Year => return write!(f, "a year like 2021"),
+            Expected::UtcOffset => return write!(f, "utc or an offset like +02:00"),
+            Expected::File => return write!(f, "a file"),
+            Expected::Scope => {
+                write!(f, "cms, any or ")?;
+                &Fractal::KEYWORDS[..]
+            }
+            Expected::OneOf(keywords) => keywords,
+        };
+
+        write!(f, "one of ")?;
+        for (i, keyword) in keywords.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", keyword)?;
+        }
+        Ok(())
+    }
+}
+
+/// Why the command line couldn't be read.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum OptError {
+    UnknownArg(&'static str),
+    /// A flag some other command takes.
+    NotAccepted {
+        flag: &'static str,
+        command: Command,
+    },
+    MissingValue(&'static str),
+    /// A value for a flag that doesn't take one.
+    UnexpectedValue(&'static str),
+    Duplicate(&'static str),
+    Conflict(&'static str, &'static str),
+    InvalidValue {
+        flag: &'static str,
+        value: &'static str,
+        expected: Expected,
+    },
+    TooManyTargets,
+    TooManyInstabs,
+    QueryTooLong,
+    Query(QueryError),
+    TeamFile(&'static str, Option<TeamError>),
+    ConfigFile(ReadError),
+    /// A config file line that couldn't be used, with its key when known.
+    Config {
+        line: usize,
+        key: Option<&'static str>,
+        expected: Expected,
+    },
+}
+
+impl OptError {
+    pub const fn exit_code(&self) -> isize {
+        match self {
+            // the command line is fine, the file isn't
+            OptError::TeamFile(..) | OptError::ConfigFile(_) | OptError::Config { .. } => {
+                EXIT_FAILURE
+            }
+            _ => EXIT_USAGE,
+        }
+    }
+}
+
+impl core::fmt::Display for OptError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        use OptError::*;
+
+        match self {
+            UnknownArg(arg) => write!(f, "unknown argument '{}'", arg),
+            NotAccepted { flag, command } => write!(f, "{} doesn't take {}", command, flag),
+            MissingValue(flag) => write!(f, "missing value for {}", flag),
+            UnexpectedValue(flag) => write!(f, "{} doesn't take a value", flag),
+            Duplicate(flag) => write!(f, "{} given more than once", flag),
+            Conflict(a, b) => write!(f, "{} and {} can't be used together", a, b),
+            InvalidValue {
+                flag,
+                value,
+                expected,
+            } => {
+                let prefixed = crate::keyword::prefixed(value, expected.keywords());
+                let count = prefixed.clone().count();
+                if count > 1 {
+                    write!(f, "ambiguous value '{}' for {}, could be ", value, flag)?;
+                    for (i, keyword) in prefixed.enumerate() {
+                        let sep = match i {
+                            0 => "",
+                            _ if i + 1 == count => " or ",
+                            _ => ", ",
+                        };
+                        write!(f, "{}'{}'", sep, keyword)?;
+                    }
+                    return Ok(());
+                }
+
+                write!(f, "invalid value '{}' for {}, ", value, flag)?;
+                match expected.suggest(value) {
+                    Some(keyword) => write!(f, "did you mean '{}'?", keyword),
+                    None => write!(f, "expected {}", expected),
+                }
+            }
+            TooManyTargets => write!(f, "too many bosses and fractals, at most {}", MAX_TARGETS),
+            TooManyInstabs => write!(f, "more than three instabilities for whereis"),
+            QueryTooLong => write!(f, "query longer than {} bytes", MAX_QUERY),
+            Query(e) => write!(f, "invalid query: {}", e),
+            TeamFile(path, None) => write!(f, "cannot read team file {}", path),
+            TeamFile(path, Some(e)) => write!(f, "invalid team file {}: {}", path, e),
+            ConfigFile(e) => write!(f, "the config file {}", e),
+            Config {
+                line,
+                key: None,
+                expected,
+            } => write!(
+                f,
+                "unknown setting on line {} of the config file, expected {}",
+                line, expected
+            ),
+            Config {
+                line,
+                key: Some(key),
+                expected,
+            } => write!(
+                f,
+                "invalid value for {} on line {} of the config file, expected {}",
+                key, line, expected
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Command {
+    Help,
+    Version,
+    Today,
+    Next,
+    Find,
+    Cal,
+    Heatmap,
+    Streaks,
+    Stats,
+    Rate,
+    Whereis,
+    Export,
+    Ics,
+    Validate,
+    Completions,
+}
+
+/// Flags choosing and tuning how fractals are rated.
+const RATER_FLAGS: &[&str] = &[
+    "--boss",
+    "--fractal",
+    "--role",
+    "--team",
+    "--policy",
+    "--alt",
+];
+/// Flags for whether a day passes for every target or any.
+const MATCH_FLAGS: &[&str] = &["--all", "--any"];
+/// Flags narrowing down which days pass.
+const FILTER_FLAGS: &[&str] = &["--threshold", "--with", "--without", "--in"];
+const RANGE_FLAGS: &[&str] = &["--from", "--to"];
+/// Flags of the commands that print days.
+const OUTPUT_FLAGS: &[&str] = &["--format"];
+/// Flags every command takes.
+const GLOBAL_FLAGS: &[&str] = &["--theme", "--timezone"];
+
+impl Command {
+    /// Every command, in the order `--help` lists them.
+    pub const ALL: [Command; 15] = [
+        Command::Today,
+        Command::Next,
+        Command::Find,
+        Command::Cal,
+        Command::Heatmap,
+        Command::Streaks,
+        Command::Stats,
+        Command::Rate,
+        Command::Whereis,
+        Command::Export,
+        Command::Ics,
+        Command::Validate,
+        Command::Completions,
+        Command::Help,
+        Command::Version,
+    ];
+
+    /// The flags the command takes, besides `--help`, `--version` and
+    /// `GLOBAL_FLAGS`.
+    pub const fn flags(&self) -> &'static [&'static [&'static str]] {
+        use Command::*;
+
+        match self {
+            Today | Rate => &[RATER_FLAGS, MATCH_FLAGS, OUTPUT_FLAGS],
+            Next => &[
+                RATER_FLAGS,
+                MATCH_FLAGS,
+                FILTER_FLAGS,
+                &["--num", "--best", "--window"],
+                OUTPUT_FLAGS,
+            ],
+            Find => &[&["--num", "--alt"], OUTPUT_FLAGS],
+            Cal | Heatmap => &[RATER_FLAGS, MATCH_FLAGS, FILTER_FLAGS],
+            Streaks => &[RATER_FLAGS, MATCH_FLAGS, FILTER_FLAGS, RANGE_FLAGS],
+            Stats => &[RANGE_FLAGS],
+            Export => &[RANGE_FLAGS, OUTPUT_FLAGS],
+            Ics => &[RATER_FLAGS, FILTER_FLAGS, RANGE_FLAGS, &["--days"]],
+            Whereis => &[OUTPUT_FLAGS],
+            Validate | Completions | Help | Version => &[],
+        }
+    }
+
+    /// Whether the config file and environment matter to the command, so
+    /// `--help` works even when they are broken.
+    pub const fn uses_defaults(&self) -> bool {
+        !matches!(
+            self,
+            Command::Help | Command::Version | Command::Completions | Command::Validate
+        )
+    }
+
+    pub fn accepts(&self, flag: &str) -> bool {
+        GLOBAL_FLAGS.contains(&flag)
+            || self
+                .flags()
+                .iter()
+                .flat_map(|f| f.iter())
+                .any(|f| *f == flag)
+    }
+}
+
+impl core::str::FromStr for Command {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use Command::*;
+
+        Ok(match s {
+            "help" => Help,
+            "version" => Version,
+            "today" => Today,
+            "next" => Next,
+            "find" => Find,
+            "cal" => Cal,
+            "heatmap" => Heatmap,
+            "streaks" => Streaks,
+            "stats" => Stats,
+            "rate" => Rate,
+            "whereis" => Whereis,
+            "export" => Export,
+            "ics" => Ics,
+            "validate" => Validate,
+            "completions" => Completions,
+            _ => return Err(()),
+        })
+    }
+}
+
+impl core::fmt::Display for Command {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        use Command::*;
+
+        let name = match self {
+            Help => "help",
+            Version => "version",
+            Today => "today",
+            Next => "next",
+            Find => "find",
+            Cal => "cal",
+            Heatmap => "heatmap",
+            Streaks => "streaks",
+            Stats => "stats",
+            Rate => "rate",
+            Whereis => "whereis",
+            Export => "export",
+            Ics => "ics",
+            Validate => "validate",
+            Completions => "completions",
+        };
+
+        f.pad(name)
+    }
+}
 
 pub struct Opts {
+    pub command: Command,
     pub num: u16,
-    pub boss: Option<Boss>,
-    pub fractal: Option<Fractal>,
+    pub targets: Targets,
+    /// Whether every target has to pass the threshold, or just one.
+    pub match_all: bool,
     pub threshold: Rating,
+    pub role: Option<Role>,
+    pub team: Option<Team>,
+    pub policy: Policy,
+    pub alt_policy: AltPolicy,
+    pub query: Option<Query>,
+    pub instabs: InstabFilter,
+    /// Rank the days in `window` instead of listing them in order.
+    pub best: bool,
+    pub window: u16,
+    pub from: Option<Date>,
+    pub to: Option<Date>,
+    /// How many days `ics` looks at instead of up to `to`.
+    pub days: Option<u16>,
+    /// Year and month for `cal`.
+    pub month: Option<(u16, u8)>,
+    /// Year for `heatmap`, the schedule's own cycle when not given.
+    pub year: Option<u16>,
+    /// What `whereis` looks for.
+    pub observed: Option<Observation>,
+    /// Day for `rate`, today when not given.
+    pub date: Option<Date>,
+    /// What `completions` writes a script for.
+    pub shell: Option<Shell>,
+    pub format: Format,
+    pub theme: Theme,
+    /// Minutes east of UTC.
+    pub utc_offset: i16,
 }
 
+#[derive(Copy, Clone)]
 struct Args<'a> {
     count: usize,
     values: *const *const u8,
     phantom: PhantomData<&'a [&'a [u8]]>,
 }
 
+#[derive(Clone)]
 struct ArgsIter<'a> {
-    args: &'a Args<'a>,
+    args: Args<'a>,
     idx: usize,
 }
 
@@ -53,83 +565,892 @@ impl<'a> Args<'a> {
         }
     }
 
-    fn iter(&'a self) -> ArgsIter<'a> {
-        ArgsIter { args: self, idx: 0 }
+    fn iter(&self) -> ArgsIter<'a> {
+        ArgsIter {
+            args: *self,
+            idx: 0,
+        }
     }
 }
 
-trait ByteSliceExt {
-    fn as_str(&self) -> &str;
+trait ByteSliceExt<'a> {
+    fn as_str(&self) -> &'a str;
 }
 
-impl ByteSliceExt for &[u8] {
-    fn as_str(&self) -> &str {
+impl<'a> ByteSliceExt<'a> for &'a [u8] {
+    fn as_str(&self) -> &'a str {
         unsafe { core::str::from_utf8_unchecked(self) }
     }
 }
 
+/// A flag, known by its long name everywhere past the parser.
+#[derive(Debug)]
+pub struct Flag {
+    pub long: &'static str,
+    pub short: Option<char>,
+    /// What the value is called in `--help` and what it has to be, for
+    /// flags that take one.
+    pub value: Option<(&'static str, Expected)>,
+    /// Whether giving it more than once adds up instead of being a mistake.
+    pub repeatable: bool,
+}
+
+const fn switch(long: &'static str, short: Option<char>) -> Flag {
+    Flag {
+        long,
+        short,
+        value: None,
+        repeatable: false,
+    }
+}
+
+const fn flag(
+    long: &'static str,
+    short: Option<char>,
+    value: &'static str,
+    expected: Expected,
+) -> Flag {
+    Flag {
+        long,
+        short,
+        value: Some((value, expected)),
+        repeatable: false,
+    }
+}
+
+const fn repeatable(
+    long: &'static str,
+    short: Option<char>,
+    value: &'static str,
+    expected: Expected,
+) -> Flag {
+    Flag {
+        long,
+        short,
+        value: Some((value, expected)),
+        repeatable: true,
+    }
+}
+
+pub const FLAGS: [Flag; 23] = [
+    switch("--help", Some('h')),
+    switch("--version", Some('V')),
+    flag("--num", Some('n'), "NUM", Expected::Number),
+    repeatable(
+        "--boss",
+        Some('b'),
+        "BOSS",
+        Expected::OneOf(&Boss::KEYWORDS),
+    ),
+    repeatable(
+        "--fractal",
+        Some('f'),
+        "FRACTAL",
+        Expected::OneOf(&Fractal::KEYWORDS),
+    ),
+    switch("--all", None),
+    switch("--any", None),
+    flag(
+        "--threshold",
+        Some('t'),
+        "RATING",
+        Expected::OneOf(&Rating::KEYWORDS),
+    ),
+    flag("--role", None, "ROLE", Expected::OneOf(&Role::KEYWORDS)),
+    flag("--team", None, "FILE", Expected::File),
+    flag(
+        "--policy",
+        None,
+        "POLICY",
+        Expected::OneOf(&Policy::KEYWORDS),
+    ),
+    flag(
+        "--alt",
+        None,
+        "POLICY",
+        Expected::OneOf(&AltPolicy::KEYWORDS),
+    ),
+    repeatable(
+        "--with",
+        None,
+        "INSTAB",
+        Expected::OneOf(&Instability::KEYWORDS),
+    ),
+    repeatable(
+        "--without",
+        None,
+        "INSTAB",
+        Expected::OneOf(&Instability::KEYWORDS),
+    ),
+    flag("--in", None, "SCOPE", Expected::Scope),
+    switch("--best", None),
+    flag("--window", None, "DAYS", Expected::Number),
+    flag("--from", None, "DATE", Expected::Date),
+    flag("--to", None, "DATE", Expected::Date),
+    flag("--days", None, "DAYS", Expected::Number),
+    flag(
+        "--format",
+        None,
+        "FORMAT",
+        Expected::OneOf(&Format::KEYWORDS),
+    ),
+    flag("--theme", None, "THEME", Expected::OneOf(&Theme::KEYWORDS)),
+    flag("--timezone", None, "OFFSET", Expected::UtcOffset),
+];
+
+/// Flags that can't be given together.
+const CONFLICTS: [(&str, &str); 3] = [("--all", "--any"), ("--role", "--team"), ("--days", "--to")];
+
+/// Whether `flag` is among the `seen` bits of `FLAGS`.
+fn given(seen: u32, flag: &str) -> bool {
+    let index = FLAGS.iter().position(|f| f.long == flag);
+    index.is_some_and(|i| seen & 1 << i != 0)
+}
+
+enum Token {
+    /// Index into `FLAGS`.
+    Flag(usize),
+    Positional(&'static str),
+}
+
+/// Splits argv into flags and positional arguments: `--name=value`, `-n7`,
+/// bundled short flags like `-hV` and `--` ending the flags.
+struct Tokens {
+    args: ArgsIter<'static>,
+    /// A value attached to the last flag, after `=` or in the same bundle.
+    value: Option<&'static str>,
+    /// The rest of a bundle of short flags, and the whole argument.
+    bundle: Option<(&'static str, &'static str)>,
+    /// Whether `--` was seen.
+    positional_only: bool,
+}
+
+impl Tokens {
+    fn new(args: ArgsIter<'static>) -> Self {
+        Self {
+            args,
+            value: None,
+            bundle: None,
+            positional_only: false,
+        }
+    }
+
+    /// The value of the last flag, either attached to it or the next
+    /// argument.
+    fn raw_value(&mut self, flag: &'static str) -> Result<&'static str, OptError> {
+        match self.value.take() {
+            Some(value) => Ok(value),
+            None => self
+                .args
+                .next()
+                .map(|arg| arg.as_str())
+                .ok_or(OptError::MissingValue(flag)),
+        }
+    }
+
+    /// The value of `FLAGS[index]`, parsed.
+    fn value<T: FromStr>(&mut self, index: usize) -> Result<T, OptError> {
+        let flag = FLAGS[index].long;
+        let (_, expected) = FLAGS[index].value.expect("flag takes a value");
+        let value = self.raw_value(flag)?;
+        value.parse().map_err(|_| OptError::InvalidValue {
+            flag,
+            value,
+            expected,
+        })
+    }
+
+    fn short(&mut self, bundle: &'static str, arg: &'static str) -> Result<Token, OptError> {
+        let mut chars = bundle.chars();
+        let short = chars.next();
+        let index = FLAGS
+            .iter()
+            .position(|f| f.short.is_some() && f.short == short)
+            .ok_or(OptError::UnknownArg(arg))?;
+
+        let rest = chars.as_str();
+        if !rest.is_empty() {
+            if FLAGS[index].value.is_some() {
+                self.value = Some(rest);
+            } else {
+                self.bundle = Some((rest, arg));
+            }
+        }
+        Ok(Token::Flag(index))
+    }
+}
+
+impl Iterator for Tokens {
+    type Item = Result<Token, OptError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.value = None;
+        if let Some((bundle, arg)) = self.bundle.take() {
+            return Some(self.short(bundle, arg));
+        }
+
+        let arg = self.args.next()?.as_str();
+        if self.positional_only || arg == "-" || !arg.starts_with('-') {
+            return Some(Ok(Token::Positional(arg)));
+        }
+        if arg == "--" {
+            self.positional_only = true;
+            return self.next();
+        }
+
+        Some(match arg.strip_prefix("--") {
+            Some(long) => {
+                let (name, value) = match long.find('=') {
+                    Some(i) => (&arg[..i + 2], Some(&long[i + 1..])),
+                    None => (arg, None),
+                };
+                match FLAGS.iter().position(|f| f.long == name) {
+                    Some(index) if FLAGS[index].value.is_none() && value.is_some() => {
+                        Err(OptError::UnexpectedValue(FLAGS[index].long))
+                    }
+                    Some(index) => {
+                        self.value = value;
+                        Ok(Token::Flag(index))
+                    }
+                    None => Err(OptError::UnknownArg(arg)),
+                }
+            }
+            None => self.short(&arg[1..], arg),
+        })
+    }
+}
+
 impl Opts {
-    pub fn parse(argc: isize, argv: *const *const u8) -> Self {
+    /// Parses the command line on top of the config file and environment.
+    pub fn parse(argc: isize, argv: *const *const u8) -> Result<Self, OptError> {
+        Self::parse_with(argc, argv, Defaults::load)
+    }
+
+    /// Like `parse`, but with the defaults from `defaults`, which is only
+    /// called once the command line is known to need them.
+    pub fn parse_with(
+        argc: isize,
+        argv: *const *const u8,
+        defaults: impl FnOnce() -> Result<Defaults, OptError>,
+    ) -> Result<Self, OptError> {
         let mut opts = Self {
+            command: Command::Next,
             num: 1,
-            boss: None,
-            fractal: None,
+            targets: Targets::new(),
+            match_all: true,
             threshold: Rating::Unplayable,
+            role: None,
+            team: None,
+            policy: Policy::Worst,
+            alt_policy: AltPolicy::Worst,
+            query: None,
+            instabs: InstabFilter::new(),
+            best: false,
+            window: 30,
+            from: None,
+            to: None,
+            days: None,
+            month: None,
+            year: None,
+            observed: None,
+            date: None,
+            shell: None,
+            format: Format::Text,
+            theme: Theme::Default,
+            utc_offset: 0,
         };
 
-        let args = Args::new(argc, argv);
+        // argv outlives everything else in the program
+        let args: Args<'static> = Args::new(argc, argv);
         let mut args_iter = args.iter();
         args_iter.next();
 
-        while let Some(arg) = args_iter.next() {
-            match arg {
-                b"-n" => {
-                    if let Some(num) = args_iter
-                        .next()
-                        .and_then(|s| s.as_str().parse::<u16>().ok())
-                    {
-                        opts.num = num;
-                    } else {
-                        panic!("no matching arg for -n");
+        // without a command, the options are the ones of `next`
+        if let Some(command) = args_iter
+            .clone()
+            .next()
+            .and_then(|arg| Command::from_str(arg.as_str()).ok())
+        {
+            opts.command = command;
+            args_iter.next();
+        }
+
+        let mut tokens = Tokens::new(args_iter);
+        let mut seen = 0u32;
+        // find's query, joined back together from however many arguments
+        let mut query = [0u8; MAX_QUERY];
+        let mut query_len = 0;
+        while let Some(token) = tokens.next() {
+            let command = opts.command;
+            let (flag, index) = match token? {
+                Token::Positional(arg) if command == Command::Find => {
+                    let start = query_len + (query_len > 0) as usize;
+                    let end = start + arg.len();
+                    if end > MAX_QUERY {
+                        return Err(OptError::QueryTooLong);
                     }
+                    query[query_len..start].fill(b' ');
+                    query[start..end].copy_from_slice(arg.as_bytes());
+                    query_len = end;
+                    continue;
                 }
-                b"-b" => {
-                    if let Some(boss) = args_iter
-                        .next()
-                        .and_then(|s| Boss::from_str(s.as_str()).ok())
-                    {
-                        opts.boss = Some(boss);
-                    } else {
-                        panic!("no matching arg for -b");
-                    }
+                Token::Positional(arg) => {
+                    opts.positional(arg)?;
+                    continue;
+                }
+                Token::Flag(index) => (FLAGS[index].long, index),
+            };
+
+            match flag {
+                "--help" => {
+                    opts.command = Command::Help;
+                    return Ok(opts);
+                }
+                "--version" => {
+                    opts.command = Command::Version;
+                    return Ok(opts);
+                }
+                _ if !command.accepts(flag) => {
+                    return Err(OptError::NotAccepted { flag, command });
                 }
-                b"-f" => {
-                    if let Some(frac) = args_iter
-                        .next()
-                        .and_then(|s| Fractal::from_str(s.as_str()).ok())
-                    {
-                        opts.fractal = Some(frac);
-                        opts.boss = None;
-                    } else {
-                        panic!("no matching arg for -f");
+                _ if seen & 1 << index != 0 && !FLAGS[index].repeatable => {
+                    return Err(OptError::Duplicate(flag));
+                }
+                _ => {}
+            }
+            seen |= 1 << index;
+            if let Some(&(a, b)) = CONFLICTS
+                .iter()
+                .find(|&&(a, b)| given(seen, a) && given(seen, b))
+            {
+                return Err(OptError::Conflict(a, b));
+            }
+
+            match flag {
+                "--num" => opts.num = tokens.value(index)?,
+                "--boss" => {
+                    let boss = tokens.value(index)?;
+                    opts.targets.push(Target::Boss(boss))?;
+                }
+                "--fractal" => {
+                    let fractal = tokens.value(index)?;
+                    opts.targets.push(Target::Fractal(fractal))?;
+                }
+                "--from" => opts.from = Some(tokens.value(index)?),
+                "--to" => opts.to = Some(tokens.value(index)?),
+                "--days" => opts.days = Some(tokens.value(index)?),
+                "--best" => opts.best = true,
+                "--window" => opts.window = tokens.value(index)?,
+                "--all" => opts.match_all = true,
+                "--any" => opts.match_all = false,
+                "--threshold" => opts.threshold = tokens.value(index)?,
+                "--role" => opts.role = Some(tokens.value(index)?),
+                "--team" => {
+                    let path = tokens.raw_value(flag)?;
+                    let mut buf = [0u8; 4096];
+                    let data = crate::fs::read_to_buf(path.as_bytes(), &mut buf)
+                        .map_err(|_| OptError::TeamFile(path, None))?;
+                    match Team::parse(data) {
+                        Ok(team) => opts.team = Some(team),
+                        Err(e) => return Err(OptError::TeamFile(path, Some(e))),
                     }
                 }
-                b"-t" => {
-                    if let Some(rating) = args_iter
-                        .next()
-                        .and_then(|s| Rating::from_str(s.as_str()).ok())
-                    {
-                        opts.threshold = rating;
-                    } else {
-                        panic!("no matching arg for -t");
+                "--policy" => opts.policy = tokens.value(index)?,
+                "--alt" => opts.alt_policy = tokens.value(index)?,
+                "--with" => {
+                    opts.instabs.with(tokens.value(index)?);
+                }
+                "--without" => {
+                    opts.instabs.without(tokens.value(index)?);
+                }
+                "--in" => {
+                    opts.instabs.scope(tokens.value(index)?);
+                }
+                "--format" => opts.format = tokens.value(index)?,
+                "--theme" => opts.theme = tokens.value(index)?,
+                "--timezone" => {
+                    let value = tokens.raw_value(flag)?;
+                    opts.utc_offset = parse_utc_offset(value).ok_or(OptError::InvalidValue {
+                        flag,
+                        value,
+                        expected: Expected::UtcOffset,
+                    })?;
+                }
+                _ => unreachable!("{} is in FLAGS but not handled", flag),
+            }
+        }
+
+        if query_len > 0 {
+            // put together from whole arguments, so still UTF-8
+            let src = core::str::from_utf8(&query[..query_len]).unwrap();
+            opts.query = Some(Query::new(src).map_err(OptError::Query)?);
+        }
+
+        // defaults only apply where the flag would and wasn't given, and are
+        // loaded last so a broken config file can't get in the way of --help
+        if opts.command.uses_defaults() {
+            let defaults = defaults()?;
+            let command = opts.command;
+            let unset = |flag| command.accepts(flag) && !given(seen, flag);
+            if unset("--boss") && unset("--fractal") {
+                if let Some(targets) = defaults.targets {
+                    opts.targets = targets;
+                }
+            }
+            if let Some(threshold) = defaults.threshold.filter(|_| unset("--threshold")) {
+                opts.threshold = threshold;
+            }
+            if let Some(theme) = defaults.theme.filter(|_| unset("--theme")) {
+                opts.theme = theme;
+            }
+            if let Some(offset) = defaults.utc_offset.filter(|_| unset("--timezone")) {
+                opts.utc_offset = offset;
+            }
+        }
+
+        match opts.command {
+            Command::Whereis if opts.observed.is_none() => {
+                return Err(OptError::MissingValue("whereis"))
+            }
+            Command::Find if opts.query.is_none() => return Err(OptError::MissingValue("find")),
+            Command::Completions if opts.shell.is_none() => {
+                return Err(OptError::MissingValue("completions"))
+            }
+            Command::Rate | Command::Ics if opts.targets.is_empty() => {
+                for &boss in &Boss::ALL {
+                    opts.targets.push(Target::Boss(boss))?;
+                }
+            }
+            _ => {}
+        }
+
+        Ok(opts)
+    }
+
+    /// `--from` and `--to` when either is given. `--from` defaults to today
+    /// and `--to` to the end of that year.
+    pub fn date_range(&self) -> Option<(Date, Date)> {
+        if self.from.is_none() && self.to.is_none() {
+            return None;
+        }
+        let from = self.from.unwrap_or_else(Date::today);
+        let to = self
+            .to
+            .unwrap_or_else(|| Date::from_ymd(from.year(), 12, 31));
+        Some((from, to))
+    }
+
+    /// The days `ics` looks at: `--days` of them when given, else like
+    /// `export`.
+    pub fn ics_days(&self) -> impl Iterator<Item = Date> + '_ {
+        let (from, to) = self.date_range().unwrap_or_else(year_ahead);
+        core::iter::successors(Some(from), |d| Some(d.successor()))
+            .take_while(move |&d| self.days.is_some() || d <= to)
+            .take(self.days.map_or(usize::MAX, usize::from))
+    }
+
+    /// How `rater` rates the fractal of `target` on `date`, when it passes
+    /// the filters on its own.
+    pub fn rate_target(&self, date: Date, target: &Target, rater: &impl Rater) -> Option<Rating> {
+        if !self
+            .instabs
+            .days(Scope::Fractal(target.fractal()))
+            .contains(date)
+        {
+            return None;
+        }
+
+        let set = Set::parse(date);
+        let daily = set
+            .find_fractal(&target.fractal())
+            .and_then(|r| set.get(&r))?;
+        let rating = daily.rate_with_alt(rater, self.alt_policy);
+        let passes = match rater.rated_days(self.threshold) {
+            Some(days) => days.contains(date),
+            None => rating >= self.threshold,
+        };
+        passes.then_some(rating)
+    }
+
+    /// Handles an argument that isn't a flag, which only some commands take.
+    fn positional(&mut self, arg: &'static str) -> Result<(), OptError> {
+        let command = self.command;
+        let invalid = |expected| {
+            Err(OptError::InvalidValue {
+                flag: match command {
+                    Command::Whereis => "whereis",
+                    Command::Cal => "cal",
+                    Command::Heatmap => "heatmap",
+                    Command::Completions => "completions",
+                    _ => "rate",
+                },
+                value: arg,
+                expected,
+            })
+        };
+
+        match command {
+            Command::Whereis => {
+                // prefixes of fractals could be instabilities, so only a
+                // fractal's full name or keyword counts
+                if self.observed.is_none() {
+                    if let Some(fractal) = Fractal::from_exact(arg) {
+                        self.observed = Some(Observation::new(Some(fractal)));
+                        return Ok(());
                     }
                 }
-                _ => {
-                    panic!("invalid arg {}", arg.as_str());
+                let observed = self.observed.get_or_insert(Observation::new(None));
+                match Instability::from_str(arg) {
+                    Ok(instab) => observed
+                        .push(instab)
+                        .map(|_| ())
+                        .map_err(|_| OptError::TooManyInstabs),
+                    Err(_) => invalid(Expected::OneOf(&Instability::KEYWORDS)),
                 }
             }
+            Command::Cal if self.month.is_none() => match parse_month(arg) {
+                Some(month) => {
+                    self.month = Some(month);
+                    Ok(())
+                }
+                None => invalid(Expected::Month),
+            },
+            Command::Heatmap if self.year.is_none() => match arg.parse::<u16>() {
+                Ok(year) if year >= 1970 => {
+                    self.year = Some(year);
+                    Ok(())
+                }
+                _ => invalid(Expected::Year),
+            },
+            Command::Rate if self.date.is_none() => match Date::from_str(arg) {
+                Ok(date) => {
+                    self.date = Some(date);
+                    Ok(())
+                }
+                Err(_) => invalid(Expected::Date),
+            },
+            Command::Completions if self.shell.is_none() => match Shell::from_str(arg) {
+                Ok(shell) => {
+                    self.shell = Some(shell);
+                    Ok(())
+                }
+                Err(_) => invalid(Expected::OneOf(&Shell::KEYWORDS)),
+            },
+            _ => Err(OptError::UnknownArg(arg)),
+        }
+    }
+}
+
+/// Parses `M` (in the current year) or `YYYY-MM`.
+fn parse_month(s: &str) -> Option<(u16, u8)> {
+    let (year, month) = match s.find('-') {
+        Some(i) => (s[..i].parse::<u16>().ok()?, &s[i + 1..]),
+        None => (Date::today().year(), s),
+    };
+    let month = month.parse::<u8>().ok()?;
+    if (1..=12).contains(&month) && year >= 1970 {
+        Some((year, month))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::Plain;
+    use core::fmt::Write;
+
+    fn parse(args: &[&'static [u8]]) -> Result<Opts, OptError> {
+        // leave the user's config and environment out of it
+        parse_with(args, Ok(Defaults::new()))
+    }
+
+    fn parse_with(
+        args: &[&'static [u8]],
+        defaults: Result<Defaults, OptError>,
+    ) -> Result<Opts, OptError> {
+        // each arg is nul terminated like the real argv
+        let mut argv = [core::ptr::null(); 8];
+        argv[0] = b"stab\0".as_ptr();
+        for (ptr, arg) in argv[1..].iter_mut().zip(args) {
+            *ptr = arg.as_ptr();
         }
+        Opts::parse_with(args.len() as isize + 1, argv.as_ptr(), || defaults)
+    }
+
+    #[test]
+    fn keywords_parse() {
+        assert!(Boss::KEYWORDS.iter().all(|k| Boss::from_str(k).is_ok()));
+        assert!(Fractal::KEYWORDS
+            .iter()
+            .all(|k| Fractal::from_str(k).is_ok()));
+        assert!(Instability::KEYWORDS
+            .iter()
+            .all(|k| Instability::from_str(k).is_ok()));
+        assert!(Rating::KEYWORDS.iter().all(|k| Rating::from_str(k).is_ok()));
+        assert!(Role::KEYWORDS.iter().all(|k| Role::from_str(k).is_ok()));
+        assert!(Policy::KEYWORDS.iter().all(|k| Policy::from_str(k).is_ok()));
+        assert!(AltPolicy::KEYWORDS
+            .iter()
+            .all(|k| AltPolicy::from_str(k).is_ok()));
+    }
+
+    #[test]
+    fn lenient_keywords() {
+        assert_eq!(Boss::from_str("ARKK"), Ok(Boss::Arkk));
+        assert_eq!(Boss::from_str("ens"), Ok(Boss::Ensolyss));
+        assert_eq!(Boss::from_str("Skorvald"), Ok(Boss::Skorvald));
+        assert_eq!(Boss::from_str("art"), Ok(Boss::Artsariiv));
+        assert_eq!(Boss::from_str("a"), Err(()));
+        assert_eq!(Fractal::from_str("so"), Ok(Fractal::ShatteredObservatory));
+        assert_eq!(Fractal::from_str("solid"), Ok(Fractal::SolidOcean));
+        assert_eq!(Fractal::from_str("Solid Ocean"), Ok(Fractal::SolidOcean));
+        assert_eq!(Fractal::from_str("molten-boss"), Ok(Fractal::MoltenBoss));
+        assert_eq!(
+            Fractal::from_str("thaumanova"),
+            Ok(Fractal::ThaumanovaReactor)
+        );
+        assert_eq!(Fractal::from_str("molten boss"), Ok(Fractal::MoltenBoss));
+        assert_eq!(Fractal::from_str("molten"), Err(()));
+        assert_eq!(
+            Instability::from_str("We Bleed Fire"),
+            Ok(Instability::WeBleedFire)
+        );
+        assert_eq!(
+            Instability::from_str("we-bleed"),
+            Ok(Instability::WeBleedFire)
+        );
+        assert_eq!(
+            Instability::from_str("No Pain, No Gain"),
+            Ok(Instability::NoPainNoGain)
+        );
+        assert_eq!(Instability::from_str("toxic"), Err(()));
+        assert_eq!(Rating::from_str("Unplay"), Ok(Rating::Unplayable));
+        assert_eq!(Rating::from_str("p"), Err(()));
+
+        assert_eq!(
+            Expected::OneOf(&Boss::KEYWORDS).suggest("sixa"),
+            Some("siax")
+        );
+        assert_eq!(Expected::Scope.suggest("volcano"), Some("volcanic"));
+        assert_eq!(Expected::OneOf(&Boss::KEYWORDS).suggest("lich"), None);
+    }
+
+    #[test]
+    fn ambiguous_values() {
+        let message = |args: &[&'static [u8]], expected: &str| {
+            let mut buf = [0u8; 256];
+            let mut out = Plain(&mut buf, 0);
+            write!(out, "{}", parse(args).err().unwrap()).unwrap();
+            let len = out.1;
+            assert_eq!(core::str::from_utf8(&buf[..len]).unwrap(), expected);
+        };
+
+        message(
+            &[b"-f\0", b"m\0"],
+            "ambiguous value 'm' for --fractal, could be 'mai' or 'molten-boss'",
+        );
+        message(
+            &[b"-b\0", b"a\0"],
+            "ambiguous value 'a' for --boss, could be 'arts', 'arkk' or 'ai'",
+        );
+        message(
+            &[b"whereis\0", b"toxic\0"],
+            "ambiguous value 'toxic' for whereis, could be 'toxic-trail' or 'toxic-sickness'",
+        );
+        message(
+            &[b"-f\0", b"volcanik\0"],
+            "invalid value 'volcanik' for --fractal, did you mean 'volcanic'?",
+        );
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            parse(&[b"-b\0"]).err(),
+            Some(OptError::MissingValue("--boss"))
+        );
+        assert_eq!(
+            parse(&[b"-b\0", b"foo\0"]).err(),
+            Some(OptError::InvalidValue {
+                flag: "--boss",
+                value: "foo",
+                expected: Expected::OneOf(&Boss::KEYWORDS),
+            })
+        );
+        assert_eq!(
+            parse(&[b"--bogus\0"]).err(),
+            Some(OptError::UnknownArg("--bogus"))
+        );
+        assert_eq!(
+            parse(&[b"whereis\0"]).err(),
+            Some(OptError::MissingValue("whereis"))
+        );
+        assert_eq!(
+            parse(&[b"-n\0", b"x\0", b"--help\0"])
+                .err()
+                .map(|e| e.exit_code()),
+            Some(EXIT_USAGE)
+        );
+        assert_eq!(
+            parse(&[b"--help\0", b"-n\0", b"x\0"]).unwrap().command,
+            Command::Help
+        );
+    }
+
+    #[test]
+    fn find_joins_its_arguments() {
+        let opts = parse(&[b"find\0", b"cm:arkk\0", b"and\0", b"rating>=good\0"]);
+        assert!(opts.unwrap().query.is_some());
+        assert!(parse(&[b"find\0", b"cm:arkk and\0", b"not\0", b"has:frailty\0"]).is_ok());
+        assert_eq!(
+            parse(&[b"find\0", b"cm:arkk\0", b"and\0", b"colour:red\0"]).err(),
+            Some(OptError::Query(QueryError::UnknownKey(12)))
+        );
+        assert_eq!(
+            parse(&[b"find\0", b"cm:arkk\0", b"and\0"]).err(),
+            Some(OptError::Query(QueryError::UnexpectedEnd))
+        );
+
+        static LONG: [u8; MAX_QUERY] = {
+            let mut arg = [b'x'; MAX_QUERY];
+            arg[MAX_QUERY - 1] = 0;
+            arg
+        };
+        assert_eq!(
+            parse(&[b"find\0", b"cm:ai\0", &LONG]).err(),
+            Some(OptError::QueryTooLong)
+        );
+    }
+
+    #[test]
+    fn commands() {
+        let opts = parse(&[b"-n\0", b"3\0"]).unwrap();
+        assert_eq!((opts.command, opts.num), (Command::Next, 3));
+        assert_eq!(parse(&[b"today\0"]).unwrap().num, 1);
+        assert_eq!(parse(&[b"rate\0"]).unwrap().targets.len(), Boss::ALL.len());
+        assert!(parse(&[b"find\0", b"cm:arkk\0"]).unwrap().query.is_some());
+        assert_eq!(
+            parse(&[b"stats\0", b"-b\0", b"arkk\0"]).err(),
+            Some(OptError::NotAccepted {
+                flag: "--boss",
+                command: Command::Stats,
+            })
+        );
+        // only the first argument names a command
+        assert_eq!(
+            parse(&[b"-n\0", b"3\0", b"stats\0"]).err(),
+            Some(OptError::UnknownArg("stats"))
+        );
+    }
+
+    #[test]
+    fn defaults() {
+        let defaults = Defaults::parse(b"boss = arkk, ai\nthreshold = good\ntheme = plain");
+        let parse = |args: &[&'static [u8]]| parse_with(args, defaults).unwrap();
+
+        let opts = parse(&[]);
+        assert_eq!((opts.targets.len(), opts.threshold), (2, Rating::Good));
+        assert_eq!(opts.theme, Theme::Plain);
+        // flags replace the configured bosses instead of adding to them
+        let opts = parse(&[b"-f\0", b"volcanic\0", b"-t\0", b"bad\0"]);
+        assert_eq!((opts.targets.len(), opts.threshold), (1, Rating::Bad));
+        assert_eq!(parse(&[b"--theme=bright\0"]).theme, Theme::Bright);
+        // stats takes no bosses, so none are configured for it
+        assert!(parse(&[b"stats\0"]).targets.is_empty());
+
+        // broken defaults only matter to commands that use them
+        let broken = Err(OptError::ConfigFile(ReadError::TooLarge));
+        assert_eq!(
+            parse_with(&[b"--help\0"], broken).unwrap().command,
+            Command::Help
+        );
+        assert_eq!(
+            parse_with(&[b"-V\0"], broken).unwrap().command,
+            Command::Version
+        );
+        assert_eq!(
+            parse_with(&[b"completions\0", b"bash\0"], broken)
+                .unwrap()
+                .command,
+            Command::Completions
+        );
+        assert_eq!(parse_with(&[], broken).err(), broken.err());
+    }
+
+    #[test]
+    fn combine_targets() {
+        use Rating::*;
+
+        let mut targets = Targets::new();
+        targets.push(Target::Boss(Boss::Arkk)).unwrap();
+        targets.push(Target::Boss(Boss::Ai)).unwrap();
+        targets.push(Target::Boss(Boss::Arkk)).unwrap();
+        assert_eq!(targets.len(), 2);
+
+        let day = |rating, score| Some(DayRating { rating, score });
+        let arkk_fails = |t: &Target| match t {
+            Target::Boss(Boss::Arkk) => None,
+            _ => day(Good, 30),
+        };
+        let both_pass = |t: &Target| match t {
+            Target::Boss(Boss::Arkk) => day(Perfect, 40),
+            _ => day(Unplayable, 10),
+        };
+
+        assert_eq!(targets.combine(true, arkk_fails), None);
+        assert_eq!(targets.combine(false, arkk_fails), day(Good, 30));
+        // the worst rating wins like for a whole day, on the same scale
+        assert_eq!(targets.combine(true, both_pass), day(Unplayable, 25));
+        assert_eq!(targets.combine(false, both_pass), day(Perfect, 40));
+        assert_eq!(Targets::new().combine(true, both_pass), None);
+    }
+
+    #[test]
+    fn gnu_syntax() {
+        let opts = parse(&[b"--boss=arkk\0", b"-n7\0", b"--threshold\0", b"good\0"]).unwrap();
+        assert_eq!(opts.targets.len(), 1);
+        assert_eq!((opts.num, opts.threshold), (7, Rating::Good));
+        assert_eq!(parse(&[b"-hV\0"]).unwrap().command, Command::Help);
+        assert_eq!(parse(&[b"-fvolcanic\0"]).unwrap().targets.len(), 1);
+
+        let opts = parse(&[b"whereis\0", b"--\0", b"flux\0"]).unwrap();
+        assert!(opts.observed.is_some());
+        // a prefix of a fractal isn't taken for one here
+        assert!(parse(&[b"whereis\0", b"sun\0"]).is_err());
+        assert!(parse(&[b"whereis\0", b"sunqua\0", b"flux\0"]).is_ok());
+        // nightmare: no pain no gain, we bleed fire, sugar rush
+        let date = Date::from_ymd(2026, 10, 19);
+        let set = <crate::Set as crate::Parsable>::parse(date);
+        let opts = parse(&[b"whereis\0", b"nm\0", b"sugar\0", b"we-bleed\0"]).unwrap();
+        assert_eq!(opts.observed.unwrap().matching(set).count(), 1);
+        assert_eq!(
+            parse(&[b"cal\0", b"--\0", b"--all\0"]).err(),
+            Some(OptError::InvalidValue {
+                flag: "cal",
+                value: "--all",
+                expected: Expected::Month,
+            })
+        );
 
-        opts
+        assert_eq!(
+            parse(&[b"-n\0", b"1\0", b"--num=2\0"]).err(),
+            Some(OptError::Duplicate("--num"))
+        );
+        assert_eq!(
+            parse(&[b"--any\0", b"--all\0"]).err(),
+            Some(OptError::Conflict("--all", "--any"))
+        );
+        assert_eq!(
+            parse(&[b"--best=yes\0"]).err(),
+            Some(OptError::UnexpectedValue("--best"))
+        );
+        // --version returns before looking at the rest of the bundle
+        assert_eq!(parse(&[b"-Vx\0"]).unwrap().command, Command::Version);
+        assert_eq!(
+            parse(&[b"-bx\0"]).err().map(|e| e.exit_code()),
+            Some(EXIT_USAGE)
+        );
     }
 }
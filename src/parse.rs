@@ -1,4 +1,5 @@
 use crate::{
+    index::{instab_index, rating_index, InstabIndex, RatingIndex},
     set::{Daily, DailyOrCm, Instabs},
     Date, Fractal, Instability, Set,
 };
@@ -9,6 +10,9 @@ const fn parse_data() -> [Set; 366] {
 
 const PARSED_DATA: [Set; 366] = parse_data();
 
+pub(crate) static INSTAB_INDEX: InstabIndex = instab_index(&PARSED_DATA);
+pub(crate) static RATING_INDEX: RatingIndex = rating_index(&PARSED_DATA);
+
 pub trait Parsable {
     fn parse(date: Date) -> &'static Self;
 }
@@ -0,0 +1,531 @@
+use core::str::FromStr;
+
+use crate::{
+    date::Weekday,
+    set::{DailyBorrow, Dailylike},
+    AltPolicy, AltRateable, Boss, Date, Fractal, HasInstabs, Instability, Rating, Set,
+};
+
+/// A filter expression over the schedule, e.g.
+/// `cm:arkk and rating>=good and not has:flux-bomb and weekday in (sat,sun)`.
+///
+/// Queries are parsed once and evaluated against a single fractal of a day
+/// at a time. A `rating` is by the boss that a `cm:` joined to it with `and`
+/// names, if that boss' CM is the fractal, so `cm:arkk and rating>=good or
+/// cm:ai and rating>=good` rates each CM by its own boss. Only a `rating`
+/// right beside the `cm:` is, not one under `not` or in parentheses.
+#[derive(Debug, Copy, Clone)]
+pub struct Query {
+    nodes: [Node; MAX_NODES],
+    len: usize,
+    alt_policy: AltPolicy,
+}
+
+/// Most clauses and operators a query can have.
+const MAX_NODES: usize = 32;
+
+/// Deepest a query can nest `not`s and parentheses.
+const MAX_DEPTH: usize = 16;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum QueryError {
+    UnexpectedEnd,
+    UnexpectedToken(usize),
+    UnknownKey(usize),
+    UnknownValue(usize),
+    TooLong(usize),
+    TooDeep(usize),
+}
+
+impl core::fmt::Display for QueryError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        use QueryError::*;
+
+        match self {
+            UnexpectedEnd => write!(f, "unexpected end of query"),
+            UnexpectedToken(pos) => write!(f, "unexpected token at {}", pos),
+            UnknownKey(pos) => write!(f, "unknown key at {}", pos),
+            UnknownValue(pos) => write!(f, "unknown value at {}", pos),
+            TooLong(pos) => write!(f, "more than {} clauses at {}", MAX_NODES, pos),
+            TooDeep(pos) => write!(f, "nested more than {} deep at {}", MAX_DEPTH, pos),
+        }
+    }
+}
+
+/// A clause or operator, referring to its operands by index.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Node {
+    Or(u8, u8),
+    And(u8, u8),
+    Not(u8),
+    /// The fractal's rating, by the boss when it's the boss' CM.
+    Rating(Op, Rating, Option<Boss>),
+    Day(Op, Rating),
+    /// Bit per weekday the day may fall on.
+    Weekday(u8),
+    Cm(Boss),
+    Fractal(Fractal),
+    Has(Instability),
+}
+
+impl Query {
+    pub fn new(src: &str) -> Result<Self, QueryError> {
+        let mut parser = Parser {
+            src,
+            pos: 0,
+            last: 0,
+            depth: 0,
+            query: Self {
+                nodes: [Node::Not(0); MAX_NODES],
+                len: 0,
+                alt_policy: AltPolicy::Worst,
+            },
+        };
+        parser.run()?;
+        Ok(parser.query)
+    }
+
+    pub fn with_alt_policy(mut self, alt_policy: AltPolicy) -> Self {
+        self.alt_policy = alt_policy;
+        self
+    }
+
+    /// The boss some `cm:` names whose CM is `fractal`.
+    pub fn boss_for(&self, fractal: Fractal) -> Option<Boss> {
+        self.nodes[..self.len].iter().find_map(|node| match node {
+            Node::Cm(boss) if boss.home() == fractal => Some(*boss),
+            _ => None,
+        })
+    }
+
+    pub fn matches(&self, date: Date, set: &Set, daily: &DailyBorrow<'_>) -> bool {
+        self.len > 0 && self.eval(self.len - 1, date, set, daily)
+    }
+
+    fn eval(&self, index: usize, date: Date, set: &Set, daily: &DailyBorrow<'_>) -> bool {
+        let eval = |i: u8| self.eval(i as usize, date, set, daily);
+
+        match self.nodes[index] {
+            Node::Or(a, b) => eval(a) || eval(b),
+            Node::And(a, b) => eval(a) && eval(b),
+            Node::Not(a) => !eval(a),
+            Node::Rating(op, rating, boss) => {
+                let actual = match boss {
+                    Some(boss) if boss.home() == *daily.fractal() => {
+                        daily.rate_with_alt(&boss, self.alt_policy)
+                    }
+                    _ => daily.rate_with_alt(daily.fractal(), self.alt_policy),
+                };
+                op.apply(actual, rating)
+            }
+            Node::Day(op, rating) => op.apply(set.rate_day(self.alt_policy).rating, rating),
+            Node::Weekday(mask) => mask & 1 << date.weekday() as u8 != 0,
+            Node::Cm(boss) => *daily.fractal() == boss.home(),
+            Node::Fractal(fractal) => *daily.fractal() == fractal,
+            Node::Has(instab) => {
+                let alt = daily.alt().map(|alt| alt.raw().contains(&instab));
+                daily.instabs().raw().contains(&instab) || alt == Some(true)
+            }
+        }
+    }
+
+    /// Every CM and daily of `set` the query matches.
+    pub fn matching<'s>(
+        &'s self,
+        date: Date,
+        set: &'s Set,
+    ) -> impl Iterator<Item = DailyBorrow<'s>> + 's {
+        set.all_iter().filter(move |d| self.matches(date, set, d))
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Op {
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+}
+
+impl Op {
+    fn apply<T: Ord>(&self, lhs: T, rhs: T) -> bool {
+        match self {
+            Op::Lt => lhs < rhs,
+            Op::Le => lhs <= rhs,
+            Op::Gt => lhs > rhs,
+            Op::Ge => lhs >= rhs,
+            Op::Eq => lhs == rhs,
+            Op::Ne => lhs != rhs,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Token<'a> {
+    Word(&'a str),
+    Colon,
+    Comma,
+    LParen,
+    RParen,
+    Op(Op),
+}
+
+struct Parser<'q> {
+    src: &'q str,
+    pos: usize,
+    /// Start of the last token returned by `next`.
+    last: usize,
+    /// `not`s and parentheses around the current clause.
+    depth: usize,
+    query: Query,
+}
+
+impl<'q> Parser<'q> {
+    fn run(&mut self) -> Result<(), QueryError> {
+        self.or()?;
+        match self.next()? {
+            None => Ok(()),
+            Some(_) => Err(QueryError::UnexpectedToken(self.last)),
+        }
+    }
+
+    /// Adds `node`, returning its index.
+    fn push(&mut self, node: Node) -> Result<u8, QueryError> {
+        let query = &mut self.query;
+        if query.len == MAX_NODES {
+            return Err(QueryError::TooLong(self.last));
+        }
+        query.nodes[query.len] = node;
+        query.len += 1;
+        Ok(query.len as u8 - 1)
+    }
+
+    fn or(&mut self) -> Result<u8, QueryError> {
+        let mut node = self.and()?;
+        while self.eat_word("or")? {
+            let rhs = self.and()?;
+            node = self.push(Node::Or(node, rhs))?;
+        }
+        Ok(node)
+    }
+
+    fn and(&mut self) -> Result<u8, QueryError> {
+        let mut conjuncts = [0; MAX_NODES];
+        let mut len = 1;
+        conjuncts[0] = self.unary()?;
+        let mut node = conjuncts[0];
+        while self.eat_word("and")? {
+            let rhs = self.unary()?;
+            node = self.push(Node::And(node, rhs))?;
+            // every conjunct is a node, so this never runs out
+            conjuncts[len] = rhs;
+            len += 1;
+        }
+
+        // a boss named in this conjunction rates the ratings beside it
+        let nodes = &mut self.query.nodes;
+        let boss = conjuncts[..len]
+            .iter()
+            .find_map(|&i| match nodes[i as usize] {
+                Node::Cm(boss) => Some(boss),
+                _ => None,
+            });
+        if let Some(boss) = boss {
+            for &i in &conjuncts[..len] {
+                if let Node::Rating(_, _, by @ None) = &mut nodes[i as usize] {
+                    *by = Some(boss);
+                }
+            }
+        }
+        Ok(node)
+    }
+
+    fn unary(&mut self) -> Result<u8, QueryError> {
+        if self.eat_word("not")? {
+            let node = self.nested(Self::unary)?;
+            return self.push(Node::Not(node));
+        }
+
+        if self.peek()? == Some(Token::LParen) {
+            self.next()?;
+            let node = self.nested(Self::or)?;
+            self.expect(Token::RParen)?;
+            return Ok(node);
+        }
+
+        self.atom()
+    }
+
+    /// Parses with `parse` one level deeper, so a query can't run the stack out.
+    fn nested(
+        &mut self,
+        parse: impl FnOnce(&mut Self) -> Result<u8, QueryError>,
+    ) -> Result<u8, QueryError> {
+        if self.depth == MAX_DEPTH {
+            return Err(QueryError::TooDeep(self.last));
+        }
+        self.depth += 1;
+        let node = parse(self);
+        self.depth -= 1;
+        node
+    }
+
+    fn atom(&mut self) -> Result<u8, QueryError> {
+        let key = self.word()?;
+        let start = self.last;
+
+        let node = match key {
+            "rating" => {
+                let op = self.op()?;
+                Node::Rating(op, self.value()?, None)
+            }
+            "day" => {
+                let op = self.op()?;
+                Node::Day(op, self.value()?)
+            }
+            "weekday" => {
+                let bit = |weekday: Weekday| 1 << weekday as u8;
+                if self.eat_word("in")? {
+                    self.expect(Token::LParen)?;
+                    let mut mask = bit(self.value()?);
+                    while self.peek()? == Some(Token::Comma) {
+                        self.next()?;
+                        mask |= bit(self.value()?);
+                    }
+                    self.expect(Token::RParen)?;
+                    Node::Weekday(mask)
+                } else {
+                    self.expect(Token::Colon)?;
+                    Node::Weekday(bit(self.value()?))
+                }
+            }
+            "cm" | "boss" => {
+                self.expect(Token::Colon)?;
+                Node::Cm(self.value()?)
+            }
+            "fractal" => {
+                self.expect(Token::Colon)?;
+                Node::Fractal(self.value()?)
+            }
+            "has" => {
+                self.expect(Token::Colon)?;
+                Node::Has(self.value()?)
+            }
+            _ => return Err(QueryError::UnknownKey(start)),
+        };
+        self.push(node)
+    }
+
+    fn value<T: FromStr>(&mut self) -> Result<T, QueryError> {
+        let word = self.word()?;
+        let start = self.last;
+        T::from_str(word).map_err(|_| QueryError::UnknownValue(start))
+    }
+
+    fn op(&mut self) -> Result<Op, QueryError> {
+        match self.next()? {
+            Some(Token::Op(op)) => Ok(op),
+            Some(Token::Colon) => Ok(Op::Eq),
+            Some(_) => Err(QueryError::UnexpectedToken(self.last)),
+            None => Err(QueryError::UnexpectedEnd),
+        }
+    }
+
+    fn word(&mut self) -> Result<&'q str, QueryError> {
+        match self.next()? {
+            Some(Token::Word(word)) => Ok(word),
+            Some(_) => Err(QueryError::UnexpectedToken(self.last)),
+            None => Err(QueryError::UnexpectedEnd),
+        }
+    }
+
+    fn eat_word(&mut self, word: &str) -> Result<bool, QueryError> {
+        if self.peek()? == Some(Token::Word(word)) {
+            self.next()?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn expect(&mut self, token: Token<'_>) -> Result<(), QueryError> {
+        match self.next()? {
+            Some(t) if t == token => Ok(()),
+            Some(_) => Err(QueryError::UnexpectedToken(self.last)),
+            None => Err(QueryError::UnexpectedEnd),
+        }
+    }
+
+    fn peek(&mut self) -> Result<Option<Token<'q>>, QueryError> {
+        let (pos, last) = (self.pos, self.last);
+        let token = self.next();
+        self.pos = pos;
+        self.last = last;
+        token
+    }
+
+    fn next(&mut self) -> Result<Option<Token<'q>>, QueryError> {
+        let bytes = self.src.as_bytes();
+        while self.pos < bytes.len() && bytes[self.pos].is_ascii_whitespace() {
+            self.pos += 1;
+        }
+
+        let start = self.pos;
+        self.last = start;
+        let b = match bytes.get(start) {
+            Some(&b) => b,
+            None => return Ok(None),
+        };
+        let next = bytes.get(start + 1).copied();
+        self.pos += 1;
+
+        let token = match b {
+            b':' => Token::Colon,
+            b',' => Token::Comma,
+            b'(' => Token::LParen,
+            b')' => Token::RParen,
+            b'<' | b'>' | b'=' | b'!' => {
+                let eq = next == Some(b'=');
+                if eq {
+                    self.pos += 1;
+                }
+                Token::Op(match (b, eq) {
+                    (b'<', false) => Op::Lt,
+                    (b'<', true) => Op::Le,
+                    (b'>', false) => Op::Gt,
+                    (b'>', true) => Op::Ge,
+                    (b'=', _) => Op::Eq,
+                    (b'!', true) => Op::Ne,
+                    _ => return Err(QueryError::UnexpectedToken(start)),
+                })
+            }
+            b if is_word_byte(b) => {
+                while self.pos < bytes.len() && is_word_byte(bytes[self.pos]) {
+                    self.pos += 1;
+                }
+                Token::Word(&self.src[start..self.pos])
+            }
+            _ => return Err(QueryError::UnexpectedToken(start)),
+        };
+
+        Ok(Some(token))
+    }
+}
+
+fn is_word_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'-' || b == b'_'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Parsable;
+
+    fn matching(query: &str, date: Date) -> usize {
+        let query = Query::new(query).unwrap();
+        query.matching(date, Set::parse(date)).count()
+    }
+
+    #[test]
+    fn evaluate() {
+        // nightmare: hamstrung, fractal vindicators, sugar rush
+        // shattered: last laugh, no pain no gain, fractal vindicators
+        // snowblind: no pain no gain, mists convergence, last laugh
+        //          | hamstrung, flux bomb, stick together
+        let date = Date::from_ymd(2021, 1, 1);
+
+        assert_eq!(matching("cm:arkk", date), 1);
+        assert_eq!(matching("has:last-laugh", date), 2);
+        assert_eq!(matching("has:flux-bomb", date), 2);
+        assert_eq!(matching("not has:flux-bomb", date), 3);
+        assert_eq!(matching("fractal:snowblind or fractal:volcanic", date), 2);
+        assert_eq!(matching("cm:mama and rating>=playable", date), 1);
+        assert_eq!(matching("cm:mama and not (rating>=playable)", date), 0);
+        assert_eq!(matching("cm:skorv and rating < playable", date), 1);
+        assert_eq!(matching("weekday:fri and cm:ai", date), 1);
+        assert_eq!(matching("weekday in (sat, sun)", date), 0);
+        assert_eq!(matching("fractal:molten-boss", date), 0);
+        assert_eq!(matching("cm:arkk or cm:ai", date), 2);
+        assert_eq!(
+            matching("day<=bad and (cm:mama or fractal:snowblind)", date),
+            2
+        );
+    }
+
+    #[test]
+    fn rating_by_own_boss() {
+        // shattered: perfect for skorvald, bad for arkk; sunqua: unplayable
+        let date = Date::from_ymd(2021, 1, 21);
+
+        assert_eq!(matching("cm:skorv and rating>=good", date), 1);
+        assert_eq!(matching("cm:arkk and rating>=good", date), 0);
+        assert_eq!(
+            matching(
+                "cm:arkk and rating>=good or cm:skorv and rating>=good",
+                date
+            ),
+            1
+        );
+        assert_eq!(
+            matching("cm:arkk and rating>=good or cm:ai and rating>=good", date),
+            0
+        );
+        // in parentheses the fractal rates it, and no flux bomb either
+        assert_eq!(
+            matching("cm:skorv and (rating:perfect or has:flux-bomb)", date),
+            0
+        );
+    }
+
+    #[test]
+    fn boss_rates_only_beside_it() {
+        let query = Query::new("cm:arkk and not (rating>=good or cm:ai) and rating<good").unwrap();
+        let ratings = query.nodes[..query.len]
+            .iter()
+            .filter_map(|node| match node {
+                Node::Rating(_, _, by) => Some(*by),
+                _ => None,
+            });
+        assert!(ratings.eq([None, Some(Boss::Arkk)]));
+
+        // shattered: perfect for skorvald, worse than good by the fractal
+        let date = Date::from_ymd(2021, 1, 21);
+        assert_eq!(matching("cm:skorv and rating<perfect", date), 0);
+        assert_eq!(matching("cm:skorv and not (rating<perfect)", date), 0);
+        assert_eq!(matching("cm:skorv and not (rating>=good)", date), 1);
+    }
+
+    #[test]
+    fn errors() {
+        use QueryError::*;
+
+        assert_eq!(Query::new("cm:").unwrap_err(), UnexpectedEnd);
+        assert_eq!(Query::new("cm:nobody").unwrap_err(), UnknownValue(3));
+        assert_eq!(Query::new("colour:red").unwrap_err(), UnknownKey(0));
+        assert_eq!(
+            Query::new("has:frailty has:frailty").unwrap_err(),
+            UnexpectedToken(12)
+        );
+        assert_eq!(Query::new("(cm:arkk").unwrap_err(), UnexpectedEnd);
+        assert!(matches!(
+            Query::new("has:frailty or has:frailty or has:frailty or has:frailty or has:frailty or has:frailty or has:frailty or has:frailty or has:frailty or has:frailty or has:frailty or has:frailty or has:frailty or has:frailty or has:frailty or has:frailty or has:frailty"),
+            Err(TooLong(_))
+        ));
+        assert_eq!(
+            Query::new("not not not not not not not not not not not not not not not not cm:ai")
+                .map(|_| ()),
+            Ok(())
+        );
+        assert_eq!(
+            Query::new("not not not not not not not not not not not not not not not not not cm:ai")
+                .unwrap_err(),
+            TooDeep(64)
+        );
+        assert_eq!(
+            Query::new("((((((((((((((((((((cm:ai))))))))))))))))))))").unwrap_err(),
+            TooDeep(16)
+        );
+    }
+}
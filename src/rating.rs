@@ -1,4 +1,4 @@
-use crate::{Fractal, HasInstabs, Instability};
+use crate::{set::Dailylike, Days, Fractal, HasInstabs, Instability, Searchable, Set};
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Rating {
@@ -21,46 +21,87 @@ impl core::fmt::Display for Rating {
             Unplayable => "Unplayable",
         };
 
-        write!(f, "{}", name)
+        f.pad(name)
     }
 }
 
+impl Rating {
+    /// What `from_str` accepts, best first.
+    pub const KEYWORDS: [&'static str; 5] = ["perfect", "good", "playable", "bad", "unplayable"];
+}
+
 impl core::str::FromStr for Rating {
     type Err = ();
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        crate::keyword::lookup(s, Self::ALL.iter().copied(), &Self::KEYWORDS, Self::keyword)
+    }
+}
+
+impl Rating {
+    /// Every rating, best first.
+    pub const ALL: [Rating; 5] = [
+        Rating::Perfect,
+        Rating::Good,
+        Rating::Playable,
+        Rating::Bad,
+        Rating::Unplayable,
+    ];
+
+    fn keyword(s: &str) -> Option<Self> {
         use Rating::*;
 
-        Ok(match s {
+        Some(match s {
             "perfect" => Perfect,
             "good" => Good,
             "playable" => Playable,
             "bad" => Bad,
             "unplayable" => Unplayable,
-            _ => return Err(()),
+            _ => return None,
         })
     }
-}
 
-impl core::ops::Add for Rating {
-    type Output = Rating;
+    pub const fn score(&self) -> u8 {
+        *self as u8
+    }
 
-    fn add(self, rhs: Self) -> Self::Output {
-        use core::cmp::Ordering;
+    /// What `+` does, usable in `const` contexts.
+    pub(crate) const fn combine(self, rhs: Self) -> Self {
         use Rating::*;
 
         if matches!((self, rhs), (Perfect, Good) | (Good, Perfect)) {
             return Perfect;
         }
 
-        match self.cmp(&rhs) {
-            Ordering::Less => self,
-            Ordering::Equal => rhs,
-            Ordering::Greater => rhs,
+        if (self as u8) < (rhs as u8) {
+            self
+        } else {
+            rhs
+        }
+    }
+
+    pub const fn from_score(score: u8) -> Option<Self> {
+        use Rating::*;
+
+        match score {
+            0 => Some(Unplayable),
+            1 => Some(Bad),
+            2 => Some(Playable),
+            3 => Some(Good),
+            4 => Some(Perfect),
+            _ => None,
         }
     }
 }
 
+impl core::ops::Add for Rating {
+    type Output = Rating;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        self.combine(rhs)
+    }
+}
+
 impl core::iter::Sum for Rating {
     fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
         iter.fold(Rating::Good, core::ops::Add::add)
@@ -69,18 +110,114 @@ impl core::iter::Sum for Rating {
 
 pub trait Rateable: HasInstabs {
     fn rate(&self, rateable: &impl Rater) -> Rating {
-        self.instabs().iter().map(|i| rateable.rate_one(i)).sum()
+        rateable.rate_instabs(self.instabs().iter())
+    }
+
+    /// Finer grained than `rate`: the sum of each instability's score.
+    fn score(&self, rateable: &impl Rater) -> u8 {
+        rateable.score_instabs(self.instabs().iter())
     }
 }
 
 impl<T> Rateable for T where T: HasInstabs {}
 
+/// Which instability set decides the rating of a daily that has an
+/// alternate one.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AltPolicy {
+    Best,
+    Worst,
+    /// Rate every instability of both sets together.
+    Both,
+}
+
+impl AltPolicy {
+    /// What `from_str` accepts.
+    pub const KEYWORDS: [&'static str; 3] = ["best", "worst", "both"];
+}
+
+impl core::str::FromStr for AltPolicy {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use AltPolicy::*;
+
+        Ok(match s {
+            "best" => Best,
+            "worst" => Worst,
+            "both" => Both,
+            _ => return Err(()),
+        })
+    }
+}
+
+pub trait AltRateable: Dailylike {
+    fn rate_with_alt(&self, rater: &impl Rater, policy: AltPolicy) -> Rating {
+        let rating = self.instabs().rate(rater);
+        let alt = match self.alt() {
+            Some(alt) => alt,
+            None => return rating,
+        };
+
+        match policy {
+            AltPolicy::Best => rating.max(alt.rate(rater)),
+            AltPolicy::Worst => rating.min(alt.rate(rater)),
+            AltPolicy::Both => rater.rate_instabs(self.instabs().iter().chain(alt.iter())),
+        }
+    }
+
+    fn score_with_alt(&self, rater: &impl Rater, policy: AltPolicy) -> u8 {
+        let score = self.instabs().score(rater);
+        let alt = match self.alt() {
+            Some(alt) => alt.score(rater),
+            None => return score,
+        };
+
+        match policy {
+            AltPolicy::Best => score.max(alt),
+            AltPolicy::Worst => score.min(alt),
+            AltPolicy::Both => (score + alt) / 2,
+        }
+    }
+
+    /// `rate_with_alt` along with the score it breaks ties with.
+    fn day_rating(&self, rater: &impl Rater, policy: AltPolicy) -> DayRating {
+        // scores add up three instabilities
+        let score = self.score_with_alt(rater, policy) as u16 * 10 / 3;
+        DayRating {
+            rating: self.rate_with_alt(rater, policy),
+            score: score as u8,
+        }
+    }
+}
+
+impl<T> AltRateable for T where T: Dailylike {}
+
 pub trait Rater {
     fn rate_one(&self, instab: &Instability) -> Rating;
+
+    /// Rates the instabilities of a daily together. Raters standing for
+    /// several players override this to combine their ratings of the whole
+    /// daily rather than of each instability.
+    fn rate_instabs<'a>(&self, instabs: impl Iterator<Item = &'a Instability> + Clone) -> Rating {
+        instabs.map(|i| self.rate_one(i)).sum()
+    }
+
+    /// The sum of each instability's score, or a combination of those sums
+    /// like `rate_instabs`.
+    fn score_instabs<'a>(&self, instabs: impl Iterator<Item = &'a Instability> + Clone) -> u8 {
+        instabs.map(|i| self.rate_one(i).score()).sum()
+    }
+
+    /// The days of the schedule on which the rater's own CM rates at least
+    /// `threshold`, for raters that have them precomputed.
+    fn rated_days(&self, _threshold: Rating) -> Option<Days> {
+        None
+    }
 }
 
 impl Instability {
-    fn rate(&self) -> Rating {
+    const fn rate(&self) -> Rating {
         use Instability::*;
         use Rating::*;
 
@@ -107,7 +244,7 @@ impl Instability {
     }
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum Boss {
     Mama,
     Siax,
@@ -119,7 +256,17 @@ pub enum Boss {
 }
 
 impl Boss {
-    pub fn home(&self) -> Fractal {
+    pub const ALL: [Boss; 7] = [
+        Boss::Mama,
+        Boss::Siax,
+        Boss::Ensolyss,
+        Boss::Skorvald,
+        Boss::Artsariiv,
+        Boss::Arkk,
+        Boss::Ai,
+    ];
+
+    pub const fn home(&self) -> Fractal {
         use Boss::*;
         use Fractal::*;
 
@@ -135,27 +282,66 @@ impl Boss {
     }
 }
 
+impl Boss {
+    /// What `from_str` accepts, in order.
+    pub const KEYWORDS: [&'static str; 7] = ["mama", "siax", "enso", "skorv", "arts", "arkk", "ai"];
+}
+
+impl core::fmt::Display for Boss {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        use Boss::*;
+
+        let name = match self {
+            Mama => "Mama",
+            Siax => "Siax",
+            Ensolyss => "Ensolyss",
+            Skorvald => "Skorvald",
+            Artsariiv => "Artsariiv",
+            Arkk => "Arkk",
+            Ai => "Ai",
+        };
+
+        f.pad(name)
+    }
+}
+
 impl core::str::FromStr for Boss {
     type Err = ();
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        crate::keyword::lookup(s, Self::ALL.iter().copied(), &Self::KEYWORDS, Self::keyword)
+    }
+}
+
+impl Rater for Boss {
+    fn rate_one(&self, instab: &Instability) -> Rating {
+        self.rate_instab(instab)
+    }
+
+    fn rated_days(&self, threshold: Rating) -> Option<Days> {
+        Some(Set::days_rated(self, threshold))
+    }
+}
+
+impl Boss {
+    /// The keywords and the aliases players use.
+    fn keyword(s: &str) -> Option<Self> {
         use Boss::*;
 
-        Ok(match s {
+        Some(match s {
             "mama" => Mama,
             "siax" => Siax,
-            "enso" => Ensolyss,
-            "skorv" => Skorvald,
+            "enso" | "ens" => Ensolyss,
+            "skorv" | "skor" => Skorvald,
             "arts" => Artsariiv,
             "arkk" => Arkk,
             "ai" => Ai,
-            _ => return Err(()),
+            _ => return None,
         })
     }
-}
 
-impl Rater for Boss {
-    fn rate_one(&self, instab: &Instability) -> Rating {
+    /// `Rater::rate_one`, usable in `const` contexts.
+    pub(crate) const fn rate_instab(&self, instab: &Instability) -> Rating {
         use Boss::*;
         use Instability::*;
         use Rating::*;
@@ -198,21 +384,294 @@ impl Rater for Fractal {
     fn rate_one(&self, instab: &Instability) -> Rating {
         use Boss::*;
         use Fractal::*;
+        use Instability::*;
+        use Rating::*;
+
+        match self {
+            Nightmare => {
+                return [Mama, Siax, Ensolyss]
+                    .iter()
+                    .map(|b| b.rate_one(instab))
+                    .sum()
+            }
+            ShatteredObservatory => {
+                return [Skorvald, Artsariiv, Arkk]
+                    .iter()
+                    .map(|b| b.rate_one(instab))
+                    .sum()
+            }
+            Aetherblade => match instab {
+                // frizz's lasers need constant movement
+                Hamstrung => return Bad,
+                LastLaugh => return Playable,
+                _ => {}
+            },
+            CaptainMaiTrinBoss => match instab {
+                // small arena, everyone stacks on the same spot
+                ToxicTrail => return Unplayable,
+                SocialAwkwardness => return Bad,
+                LastLaugh => return Good,
+                _ => {}
+            },
+            ChaosIsles => match instab {
+                LastLaugh => return Unplayable,
+                Vengeance => return Unplayable,
+                _ => {}
+            },
+            Deepstone => match instab {
+                // the voice keeps spawning adds
+                LastLaugh => return Unplayable,
+                FluxBomb => return Bad,
+                _ => {}
+            },
+            MoltenBoss => match instab {
+                LastLaugh => return Good,
+                Hamstrung => return Bad,
+                ToxicTrail => return Unplayable,
+                _ => {}
+            },
+            MoltenFurnace => match instab {
+                LastLaugh => return Unplayable,
+                Hamstrung => return Bad,
+                _ => {}
+            },
+            SirensReef => match instab {
+                LastLaugh => return Unplayable,
+                Vengeance => return Unplayable,
+                _ => {}
+            },
+            Snowblind => match instab {
+                ToxicTrail => return Unplayable,
+                LastLaugh => return Playable,
+                _ => {}
+            },
+            Swampland => match instab {
+                FluxBomb => return Playable,
+                SocialAwkwardness => return Playable,
+                _ => {}
+            },
+            ThaumanovaReactor => match instab {
+                LastLaugh => return Unplayable,
+                Vengeance => return Unplayable,
+                _ => {}
+            },
+            TwilightOasis => match instab {
+                LastLaugh => return Good,
+                ToxicTrail => return Unplayable,
+                _ => {}
+            },
+            UndergroundFacility => match instab {
+                LastLaugh => return Unplayable,
+                Vengeance => return Unplayable,
+                _ => {}
+            },
+            Volcanic => match instab {
+                LastLaugh => return Unplayable,
+                FluxBomb => return Bad,
+                _ => {}
+            },
+            _ => {}
+        }
+        instab.rate()
+    }
+}
+
+/// Something a search can be run for: a CM boss or a whole fractal.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Target {
+    Boss(Boss),
+    Fractal(Fractal),
+}
+
+impl Target {
+    pub fn fractal(&self) -> Fractal {
+        match self {
+            Target::Boss(boss) => boss.home(),
+            Target::Fractal(fractal) => *fractal,
+        }
+    }
+}
+
+impl core::fmt::Display for Target {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Target::Boss(boss) => write!(f, "{}", boss),
+            Target::Fractal(fractal) => write!(f, "{}", fractal),
+        }
+    }
+}
 
+impl Rater for Target {
+    fn rate_one(&self, instab: &Instability) -> Rating {
+        match self {
+            Target::Boss(boss) => boss.rate_one(instab),
+            Target::Fractal(fractal) => fractal.rate_one(instab),
+        }
+    }
+
+    fn rated_days(&self, threshold: Rating) -> Option<Days> {
         match self {
-            Nightmare => [Mama, Siax, Ensolyss]
-                .iter()
-                .map(|b| b.rate_one(instab))
-                .sum(),
-            ShatteredObservatory => [Skorvald, Artsariiv, Arkk]
-                .iter()
-                .map(|b| b.rate_one(instab))
-                .sum(),
-            _ => instab.rate(),
+            Target::Boss(boss) => boss.rated_days(threshold),
+            Target::Fractal(_) => None,
         }
     }
 }
 
+/// Rating of a whole day or of some of its fractals. Orders by rating
+/// first, then by score, the mean instability score in tenths of a rating
+/// from 0 to 40 that breaks ties.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct DayRating {
+    pub rating: Rating,
+    pub score: u8,
+}
+
+impl Set {
+    /// Rates every fractal of the day once, a CM by each of its bosses, and
+    /// sums the ratings, so the worst one wins. The score is the average of
+    /// the fractals' scores, a CM's being the average over its bosses.
+    pub fn rate_day(&self, policy: AltPolicy) -> DayRating {
+        let mut rating = Rating::Good;
+        let mut score = 0u16;
+        let mut count = 0u16;
+
+        for daily in self.all_iter() {
+            let fractal = *daily.fractal();
+            let mut bosses = Boss::ALL.iter().filter(|b| b.home() == fractal).peekable();
+            let (sum, len) = if bosses.peek().is_some() {
+                bosses.fold((0u16, 0u16), |(sum, len), boss| {
+                    let day = daily.day_rating(boss, policy);
+                    rating = rating + day.rating;
+                    (sum + day.score as u16, len + 1)
+                })
+            } else {
+                let day = daily.day_rating(&fractal, policy);
+                rating = rating + day.rating;
+                (day.score as u16, 1)
+            };
+            score += sum / len;
+            count += 1;
+        }
+
+        DayRating {
+            rating,
+            score: score.checked_div(count).unwrap_or(0) as u8,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Role {
+    Healer,
+    BoonDps,
+    Kiter,
+    Dps,
+}
+
+impl Role {
+    /// What `from_str` accepts.
+    pub const KEYWORDS: [&'static str; 4] = ["healer", "boon", "kiter", "dps"];
+}
+
+impl core::str::FromStr for Role {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use Role::*;
+
+        Ok(match s {
+            "healer" => Healer,
+            "boon" => BoonDps,
+            "kiter" => Kiter,
+            "dps" => Dps,
+            _ => return Err(()),
+        })
+    }
+}
+
+impl core::fmt::Display for Role {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        use Role::*;
+
+        let name = match self {
+            Healer => "Healer",
+            BoonDps => "Boon DPS",
+            Kiter => "Kiter",
+            Dps => "DPS",
+        };
+
+        f.pad(name)
+    }
+}
+
+impl Role {
+    /// Role-specific override for an instability, taking precedence over
+    /// whatever the wrapped rater says.
+    pub(crate) fn rate_one(&self, instab: &Instability) -> Option<Rating> {
+        use Instability::*;
+        use Rating::*;
+        use Role::*;
+
+        Some(match self {
+            Healer => match instab {
+                SocialAwkwardness => Unplayable,
+                NoPainNoGain => Unplayable,
+                ToxicTrail => Unplayable,
+                Afflicted => Bad,
+                _ => return None,
+            },
+            BoonDps => match instab {
+                BoonOverload => Perfect,
+                NoPainNoGain => Unplayable,
+                Outflanked => Good,
+                _ => return None,
+            },
+            Kiter => match instab {
+                Hamstrung => Unplayable,
+                SocialAwkwardness => Playable,
+                ToxicTrail => Playable,
+                _ => return None,
+            },
+            Dps => match instab {
+                BoonOverload => Good,
+                SocialAwkwardness => Bad,
+                _ => return None,
+            },
+        })
+    }
+}
+
+/// Rates from the point of view of a single role on top of another rater.
+#[derive(Debug, Copy, Clone)]
+pub struct RoleRater<T> {
+    rater: T,
+    role: Role,
+}
+
+impl<T> RoleRater<T>
+where
+    T: Rater,
+{
+    pub const fn new(rater: T, role: Role) -> Self {
+        Self { rater, role }
+    }
+
+    pub const fn role(&self) -> Role {
+        self.role
+    }
+}
+
+impl<T> Rater for RoleRater<T>
+where
+    T: Rater,
+{
+    fn rate_one(&self, instab: &Instability) -> Rating {
+        self.role
+            .rate_one(instab)
+            .unwrap_or_else(|| self.rater.rate_one(instab))
+    }
+}
+
 impl Rater for crate::set::Nightmare {
     fn rate_one(&self, instab: &Instability) -> Rating {
         use Boss::*;
@@ -273,4 +732,124 @@ mod tests {
             assert_eq!(instabs.rate(boss), *expected);
         }
     }
+
+    #[test]
+    fn alternate_instabs() {
+        use crate::set::{Daily, Instabs};
+        use Instability::*;
+        use Rating::*;
+
+        let daily = Daily::new_with_alt(
+            Fractal::SolidOcean,
+            Instabs::new(BoonOverload, Afflicted, Frailty),
+            Some(Instabs::new(Afflicted, Frailty, Hamstrung)),
+        );
+        let daily = daily.borrow();
+        let rater = Fractal::SolidOcean;
+
+        assert_eq!(daily.rate(&rater), Perfect);
+        assert_eq!(daily.rate_with_alt(&rater, AltPolicy::Best), Perfect);
+        assert_eq!(daily.rate_with_alt(&rater, AltPolicy::Worst), Playable);
+        assert_eq!(daily.rate_with_alt(&rater, AltPolicy::Both), Playable);
+
+        let daily = Daily::new(rater, Instabs::new(Afflicted, Frailty, Hamstrung));
+        assert_eq!(daily.rate_with_alt(&rater, AltPolicy::Best), Playable);
+    }
+
+    #[test]
+    fn day_rating() {
+        use crate::{Date, Parsable};
+
+        let set = Set::parse(Date::from_ymd(2021, 1, 1));
+        let day = set.rate_day(AltPolicy::Worst);
+        assert!(day.score <= 40);
+        assert!(day <= set.rate_day(AltPolicy::Best));
+
+        let rate = |y, m, d| {
+            let day = Set::parse(Date::from_ymd(y, m, d)).rate_day(AltPolicy::Worst);
+            (day.rating, day.score)
+        };
+        assert_eq!(rate(2020, 1, 1), (Rating::Unplayable, 21));
+        assert_eq!(rate(2020, 2, 3), (Rating::Playable, 27));
+        assert_eq!(rate(2020, 2, 27), (Rating::Unplayable, 12));
+
+        // Mama can't do Nightmare, and one unplayable CM makes the day so,
+        // however the other fractals average out
+        let date = Date::from_ymd(2026, 10, 19);
+        let set = Set::parse(date);
+        let nightmare = set.all_iter().find(|d| *d.fractal() == Fractal::Nightmare);
+        let nightmare = nightmare.unwrap();
+        assert_eq!(
+            nightmare.rate_with_alt(&Boss::Mama, AltPolicy::Worst),
+            Rating::Unplayable
+        );
+        assert_eq!(rate(2026, 10, 19), (Rating::Unplayable, 19));
+        assert!(set.rate_day(AltPolicy::Worst).score >= 15);
+
+        let worse = DayRating {
+            rating: Rating::Bad,
+            score: 40,
+        };
+        let better = DayRating {
+            rating: Rating::Playable,
+            score: 0,
+        };
+        assert!(worse < better);
+    }
+
+    #[test]
+    fn role_overrides() {
+        use crate::set::Instabs;
+        use Boss::*;
+        use Instability::*;
+        use Rating::*;
+
+        let instabs = Instabs::new(BoonOverload, Hamstrung, Frailty);
+        assert_eq!(instabs.rate(&Arkk), Bad);
+        assert_eq!(instabs.rate(&RoleRater::new(Arkk, Role::Kiter)), Unplayable);
+        assert_eq!(instabs.rate(&RoleRater::new(Arkk, Role::Healer)), Bad);
+
+        let instabs = Instabs::new(SocialAwkwardness, Afflicted, Frailty);
+        assert_eq!(instabs.rate(&Skorvald), Unplayable);
+        assert_eq!(
+            instabs.rate(&RoleRater::new(Skorvald, Role::Kiter)),
+            Playable
+        );
+    }
+
+    #[test]
+    fn daily_fractal_overrides() {
+        use crate::set::Instabs;
+        use Fractal::*;
+        use Instability::*;
+        use Rating::*;
+
+        let sets = [
+            (
+                MoltenBoss,
+                Instabs::new(LastLaugh, Afflicted, Frailty),
+                Good,
+            ),
+            (
+                Deepstone,
+                Instabs::new(LastLaugh, Afflicted, Frailty),
+                Unplayable,
+            ),
+            (
+                CaptainMaiTrinBoss,
+                Instabs::new(BoonOverload, Afflicted, ToxicTrail),
+                Unplayable,
+            ),
+            (
+                Aetherblade,
+                Instabs::new(BoonOverload, Hamstrung, Frailty),
+                Bad,
+            ),
+            (SolidOcean, Instabs::new(LastLaugh, Afflicted, Frailty), Bad),
+        ];
+
+        for (fractal, instabs, expected) in &sets {
+            assert_eq!(instabs.rate(fractal), *expected);
+        }
+    }
 }
@@ -1,4 +1,8 @@
-use crate::{Date, Fractal, Instability};
+use crate::{
+    instab::INSTAB_COUNT,
+    parse::{INSTAB_INDEX, RATING_INDEX},
+    Boss, Date, Days, Fractal, Instability, Rating,
+};
 
 #[derive(Debug, Clone)]
 pub struct Set {
@@ -40,6 +44,54 @@ impl Set {
         })
     }
 
+    /// The three T4 dailies, including the ones that are CMs.
+    pub fn t4_iter(&self) -> impl Iterator<Item = DailyBorrow<'_>> {
+        self.dailies.iter().map(move |d| match d {
+            DailyOrCm::Daily(daily) => daily.borrow(),
+            DailyOrCm::Nightmare => self.nm.as_daily(),
+            DailyOrCm::ShatteredObservatory => self.so.as_daily(),
+            DailyOrCm::SunquaPeak => self.sp.as_daily(),
+        })
+    }
+
+    /// The three CMs followed by the dailies that aren't CMs, so every
+    /// fractal of the day shows up once.
+    pub fn all_iter(&self) -> impl Iterator<Item = DailyBorrow<'_>> {
+        IntoIterator::into_iter([self.nm.as_daily(), self.so.as_daily(), self.sp.as_daily()])
+            .chain(self.dailies_iter().map(Daily::borrow))
+    }
+
+    pub(crate) const fn dailies(&self) -> &[DailyOrCm; 3] {
+        &self.dailies
+    }
+
+    /// Looks for mistakes in the data: an instability twice in a fractal,
+    /// an alternate set that is the same as the first one, or a fractal
+    /// twice among the dailies.
+    pub fn check(&self) -> Result<(), SetError> {
+        let repeats = |instabs: &Instabs| {
+            let raw = instabs.raw();
+            raw[0] == raw[1] || raw[0] == raw[2] || raw[1] == raw[2]
+        };
+
+        for daily in self.all_iter() {
+            if repeats(daily.instabs) || daily.alt.is_some_and(repeats) {
+                return Err(SetError::RepeatedInstab(daily.fractal));
+            }
+            if daily.alt == Some(daily.instabs) {
+                return Err(SetError::SameAlt(daily.fractal));
+            }
+        }
+
+        for (i, daily) in self.t4_iter().enumerate() {
+            if self.t4_iter().take(i).any(|d| d.fractal == daily.fractal) {
+                return Err(SetError::RepeatedFractal(daily.fractal));
+            }
+        }
+
+        Ok(())
+    }
+
     pub const fn nightmare(&self) -> &Nightmare {
         &self.nm
     }
@@ -91,11 +143,11 @@ impl Instabs {
         &self.0[..]
     }
 
-    pub fn raw(&self) -> &[Instability; 3] {
+    pub const fn raw(&self) -> &[Instability; 3] {
         &self.0
     }
 
-    pub fn iter(&self) -> impl Iterator<Item = &Instability> {
+    pub fn iter(&self) -> impl Iterator<Item = &Instability> + Clone {
         self.0.iter()
     }
 }
@@ -140,8 +192,16 @@ impl Daily {
         &self.fractal
     }
 
+    pub(crate) const fn instab_sets(&self) -> (&Instabs, Option<&Instabs>) {
+        let alt = match &self.alt {
+            Some(alt) => Some(alt),
+            None => None,
+        };
+        (&self.instabs, alt)
+    }
+
     pub fn borrow(&self) -> DailyBorrow<'_> {
-        DailyBorrow::new(self.fractal, &self.instabs)
+        DailyBorrow::new(self.fractal, &self.instabs).with_alt(self.alt.as_ref())
     }
 
     pub fn with_alt(mut self, alt: impl Into<Option<Instabs>>) -> Self {
@@ -280,6 +340,7 @@ impl HasInstabs for SunquaPeak {
     }
 }
 
+#[derive(Clone)]
 pub struct DailyBorrow<'a> {
     fractal: Fractal,
     instabs: &'a Instabs,
@@ -318,6 +379,7 @@ impl<'a> DailyBorrow<'a> {
     }
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum SearchResult {
     Nightmare,
     ShatteredObservatory,
@@ -329,6 +391,187 @@ pub trait Searchable {
     fn date(&self) -> &Date;
     fn get(&self, search_result: &SearchResult) -> Option<DailyBorrow<'_>>;
     fn find_fractal(&self, fractal: &Fractal) -> Option<SearchResult>;
+    fn find_instab(&self, instab: &Instability, scope: &Scope) -> Option<SearchResult>;
+
+    fn has_instab(&self, instab: &Instability, scope: &Scope) -> bool {
+        self.find_instab(instab, scope).is_some()
+    }
+
+    /// Days of the schedule on which `instab` shows up in `scope`.
+    fn days_with(instab: &Instability, scope: &Scope) -> Days
+    where
+        Self: Sized;
+
+    /// Days of the schedule on which `boss` rates its CM at least
+    /// `threshold`.
+    fn days_rated(boss: &Boss, threshold: Rating) -> Days
+    where
+        Self: Sized;
+}
+
+/// Which fractals of a day an instability search looks at.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Scope {
+    Fractal(Fractal),
+    Cms,
+    Any,
+}
+
+impl Scope {
+    pub fn contains(&self, fractal: &Fractal) -> bool {
+        use Fractal::*;
+
+        match self {
+            Scope::Fractal(f) => f == fractal,
+            Scope::Cms => matches!(fractal, Nightmare | ShatteredObservatory | SunquaPeak),
+            Scope::Any => true,
+        }
+    }
+}
+
+impl core::str::FromStr for Scope {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "cms" => Scope::Cms,
+            "any" => Scope::Any,
+            _ => Scope::Fractal(Fractal::from_str(s)?),
+        })
+    }
+}
+
+/// Instabilities that must or must not show up on a day.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct InstabFilter {
+    with: u32,
+    without: u32,
+    scope: Option<Scope>,
+}
+
+impl InstabFilter {
+    pub const fn new() -> Self {
+        Self {
+            with: 0,
+            without: 0,
+            scope: None,
+        }
+    }
+
+    pub fn with(&mut self, instab: Instability) -> &mut Self {
+        self.with |= 1 << instab as u32;
+        self
+    }
+
+    pub fn without(&mut self, instab: Instability) -> &mut Self {
+        self.without |= 1 << instab as u32;
+        self
+    }
+
+    pub fn scope(&mut self, scope: Scope) -> &mut Self {
+        self.scope = Some(scope);
+        self
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.with == 0 && self.without == 0
+    }
+
+    /// The days of the schedule that pass, like `matches` for each of them.
+    pub fn days(&self, default_scope: Scope) -> Days {
+        let scope = self.scope.unwrap_or(default_scope);
+
+        (1..=INSTAB_COUNT)
+            .filter_map(Instability::from_u8)
+            .fold(Days::ALL, |days, instab| {
+                let bit = 1 << instab as u32;
+                if self.with & bit != 0 {
+                    days.and(Set::days_with(&instab, &scope))
+                } else if self.without & bit != 0 {
+                    days.and_not(Set::days_with(&instab, &scope))
+                } else {
+                    days
+                }
+            })
+    }
+
+    /// Checks `set`, looking in `default_scope` unless a scope was given.
+    pub fn matches(&self, set: &impl Searchable, default_scope: Scope) -> bool {
+        let scope = self.scope.unwrap_or(default_scope);
+
+        (1..=INSTAB_COUNT)
+            .filter_map(Instability::from_u8)
+            .all(|instab| {
+                let bit = 1 << instab as u32;
+                if self.with & bit != 0 && !set.has_instab(&instab, &scope) {
+                    return false;
+                }
+                self.without & bit == 0 || !set.has_instab(&instab, &scope)
+            })
+    }
+}
+
+/// A mistake in a day of the schedule.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SetError {
+    RepeatedInstab(Fractal),
+    SameAlt(Fractal),
+    RepeatedFractal(Fractal),
+}
+
+impl core::fmt::Display for SetError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        use SetError::*;
+
+        match self {
+            RepeatedInstab(fractal) => write!(f, "{} has an instability twice", fractal),
+            SameAlt(fractal) => write!(f, "{} has the same alternate instabilities", fractal),
+            RepeatedFractal(fractal) => write!(f, "{} is a daily twice", fractal),
+        }
+    }
+}
+
+/// Instabilities seen in game, possibly only some of them and in any order,
+/// optionally along with the fractal they were seen in.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct Observation {
+    fractal: Option<Fractal>,
+    instabs: u32,
+}
+
+impl Observation {
+    pub const fn new(fractal: Option<Fractal>) -> Self {
+        Self {
+            fractal,
+            instabs: 0,
+        }
+    }
+
+    /// Adds `instab`, failing once more than a fractal's three are given.
+    #[allow(clippy::result_unit_err)]
+    pub fn push(&mut self, instab: Instability) -> Result<&mut Self, ()> {
+        let instabs = self.instabs | 1 << instab as u32;
+        if instabs.count_ones() > 3 {
+            return Err(());
+        }
+        self.instabs = instabs;
+        Ok(self)
+    }
+
+    pub fn matches(&self, daily: &impl Dailylike) -> bool {
+        let covers = |instabs: &Instabs| {
+            let bits = instabs.iter().fold(0, |bits, i| bits | 1 << *i as u32);
+            self.instabs & !bits == 0
+        };
+
+        self.fractal.is_none_or(|f| f == daily.fractal())
+            && (covers(daily.instabs()) || daily.alt().is_some_and(covers))
+    }
+
+    /// The fractals of `set` that could be the one observed.
+    pub fn matching<'s>(&'s self, set: &'s Set) -> impl Iterator<Item = DailyBorrow<'s>> + 's {
+        set.all_iter().filter(move |d| self.matches(d))
+    }
 }
 
 impl Searchable for Set {
@@ -363,17 +606,44 @@ impl Searchable for Set {
             _ => self
                 .dailies
                 .iter()
-                .filter_map(|d| {
-                    if let DailyOrCm::Daily(daily) = d {
-                        Some(daily)
-                    } else {
-                        None
-                    }
-                })
-                .position(|d| d.fractal == *fractal)
+                .position(|d| matches!(d, DailyOrCm::Daily(daily) if daily.fractal == *fractal))
                 .map(SearchResult::Daily),
         }
     }
+
+    fn find_instab(&self, instab: &Instability, scope: &Scope) -> Option<SearchResult> {
+        use SearchResult::*;
+
+        [
+            Nightmare,
+            ShatteredObservatory,
+            SunquaPeak,
+            Daily(0),
+            Daily(1),
+            Daily(2),
+        ]
+        .iter()
+        .copied()
+        .find(|r| {
+            self.get(r).is_some_and(|daily| {
+                scope.contains(daily.fractal())
+                    && (daily.instabs.raw().contains(instab)
+                        || daily.alt.is_some_and(|alt| alt.raw().contains(instab)))
+            })
+        })
+    }
+
+    fn days_with(instab: &Instability, scope: &Scope) -> Days {
+        Fractal::all()
+            .filter(|f| scope.contains(f))
+            .fold(Days::EMPTY, |days, f| {
+                days.or(INSTAB_INDEX[f as usize][*instab as usize])
+            })
+    }
+
+    fn days_rated(boss: &Boss, threshold: Rating) -> Days {
+        RATING_INDEX[*boss as usize][threshold as usize]
+    }
 }
 
 impl core::fmt::Display for DailyBorrow<'_> {
@@ -394,18 +664,30 @@ impl HasInstabs for DailyBorrow<'_> {
 
 pub trait Dailylike: HasInstabs {
     fn fractal(&self) -> Fractal;
+
+    fn alt(&self) -> Option<&Instabs> {
+        None
+    }
 }
 
 impl Dailylike for Daily {
     fn fractal(&self) -> Fractal {
         self.fractal
     }
+
+    fn alt(&self) -> Option<&Instabs> {
+        self.alt.as_ref()
+    }
 }
 
 impl Dailylike for DailyBorrow<'_> {
     fn fractal(&self) -> Fractal {
         self.fractal
     }
+
+    fn alt(&self) -> Option<&Instabs> {
+        self.alt
+    }
 }
 
 impl Dailylike for Nightmare {
@@ -425,3 +707,77 @@ impl Dailylike for SunquaPeak {
         Fractal::SunquaPeak
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Parsable;
+
+    #[test]
+    fn find_fractal_skips_cms() {
+        // the first daily is the nightmare CM
+        let set = Set::parse(Date::from_ymd(2021, 1, 1));
+        let result = set.find_fractal(&Fractal::Snowblind).unwrap();
+        assert_eq!(result, SearchResult::Daily(1));
+        assert_eq!(*set.get(&result).unwrap().fractal(), Fractal::Snowblind);
+    }
+
+    #[test]
+    fn instab_search() {
+        use Instability::*;
+
+        // shattered: last laugh, no pain no gain, fractal vindicators
+        // snowblind: no pain no gain, mists convergence, last laugh
+        //          | hamstrung, flux bomb, stick together
+        let set = Set::parse(Date::from_ymd(2021, 1, 1));
+
+        assert_eq!(
+            set.find_instab(&LastLaugh, &Scope::Any),
+            Some(SearchResult::ShatteredObservatory)
+        );
+        assert_eq!(
+            set.find_instab(&LastLaugh, &Scope::Fractal(Fractal::Snowblind)),
+            Some(SearchResult::Daily(1))
+        );
+        assert!(set.has_instab(&FluxBomb, &Scope::Fractal(Fractal::Snowblind)));
+        assert!(!set.has_instab(&MistsConvergence, &Scope::Cms));
+
+        let mut filter = InstabFilter::new();
+        filter.with(LastLaugh).without(MistsConvergence);
+        assert!(filter.matches(set, Scope::Cms));
+        assert!(!filter.matches(set, Scope::Any));
+        assert!(filter.scope(Scope::Cms).matches(set, Scope::Any));
+    }
+
+    #[test]
+    fn observations() {
+        use Instability::*;
+
+        // nightmare: hamstrung, fractal vindicators, sugar rush
+        // snowblind: no pain no gain, mists convergence, last laugh
+        //          | hamstrung, flux bomb, stick together
+        let set = Set::parse(Date::from_ymd(2021, 1, 1));
+
+        let mut seen = Observation::new(Some(Fractal::Nightmare));
+        seen.push(SugarRush).unwrap().push(Hamstrung).unwrap();
+        assert_eq!(seen.matching(set).count(), 1);
+        seen.push(FluxBomb).unwrap();
+        assert_eq!(seen.matching(set).count(), 0);
+        assert!(seen.push(LastLaugh).is_err());
+
+        let mut seen = Observation::new(None);
+        seen.push(StickTogether).unwrap().push(FluxBomb).unwrap();
+        let found = seen.matching(set).next().unwrap();
+        assert_eq!(*found.fractal(), Fractal::Snowblind);
+        // the alt set on its own, not mixed with the first one
+        seen.push(LastLaugh).unwrap();
+        assert_eq!(seen.matching(set).count(), 0);
+    }
+
+    #[test]
+    fn dataset_is_valid() {
+        for date in crate::date::dataset_days() {
+            assert_eq!(Set::parse(date).check(), Ok(()), "{}", date);
+        }
+    }
+}
@@ -0,0 +1,213 @@
+use crate::{
+    fractal::FRACTAL_COUNT, instab::INSTAB_COUNT, set::Dailylike, Boss, Date, Fractal, Instability,
+    Parsable, Rateable, Rating, Set,
+};
+
+const RATING_COUNT: usize = 5;
+const BOSS_COUNT: usize = Boss::ALL.len();
+
+/// Gaps, in days, between a boss' Good-or-better days.
+#[derive(Debug, Copy, Clone, Default)]
+struct Gaps {
+    last: Option<u32>,
+    count: u32,
+    total: u32,
+    max: u32,
+}
+
+impl Gaps {
+    fn push(&mut self, idx: u32) {
+        if let Some(last) = self.last {
+            let gap = idx - last;
+            self.count += 1;
+            self.total += gap;
+            self.max = self.max.max(gap);
+        }
+        self.last = Some(idx);
+    }
+}
+
+/// How often instabilities and ratings come up over a stretch of days.
+#[derive(Debug, Clone)]
+pub struct Stats {
+    days: u32,
+    instabs: [[u32; INSTAB_COUNT as usize]; FRACTAL_COUNT],
+    ratings: [[u32; RATING_COUNT]; BOSS_COUNT],
+    gaps: [Gaps; BOSS_COUNT],
+}
+
+impl Stats {
+    pub fn collect(dates: impl Iterator<Item = Date>) -> Self {
+        let mut stats = Self {
+            days: 0,
+            instabs: [[0; INSTAB_COUNT as usize]; FRACTAL_COUNT],
+            ratings: [[0; RATING_COUNT]; BOSS_COUNT],
+            gaps: [Gaps::default(); BOSS_COUNT],
+        };
+
+        for (idx, date) in dates.enumerate() {
+            let set = Set::parse(date);
+            stats.days += 1;
+
+            let cms = [
+                set.nightmare().as_daily(),
+                set.shattered_observatory().as_daily(),
+                set.sunqua_peak().as_daily(),
+            ];
+            for daily in cms.iter() {
+                stats.count_instabs(daily);
+            }
+            for daily in set.dailies_iter() {
+                stats.count_instabs(daily);
+            }
+
+            for &boss in &Boss::ALL {
+                let cm = match boss.home() {
+                    Fractal::Nightmare => &cms[0],
+                    Fractal::ShatteredObservatory => &cms[1],
+                    _ => &cms[2],
+                };
+                let rating = cm.rate(&boss);
+                stats.ratings[boss as usize][rating as usize] += 1;
+                if rating >= Rating::Good {
+                    // a date range has far fewer than u32::MAX days
+                    stats.gaps[boss as usize].push(idx as u32);
+                }
+            }
+        }
+
+        stats
+    }
+
+    fn count_instabs(&mut self, daily: &impl Dailylike) {
+        let counts = &mut self.instabs[daily.fractal() as usize];
+        for instab in daily.instabs().iter() {
+            counts[*instab as usize] += 1;
+        }
+        for instab in daily.alt().iter().flat_map(|alt| alt.iter()) {
+            // only count what the main set doesn't already have
+            if !daily.instabs().raw().contains(instab) {
+                counts[*instab as usize] += 1;
+            }
+        }
+    }
+
+    pub const fn days(&self) -> u32 {
+        self.days
+    }
+
+    /// Number of days `instab` shows up in `fractal`.
+    pub fn instab_count(&self, fractal: Fractal, instab: Instability) -> u32 {
+        self.instabs[fractal as usize][instab as usize]
+    }
+
+    /// Number of days `boss` is rated `rating`.
+    pub fn rating_count(&self, boss: Boss, rating: Rating) -> u32 {
+        self.ratings[boss as usize][rating as usize]
+    }
+
+    /// Average gap in tenths of a day and largest gap between `boss`'
+    /// Good-or-better days, if there are at least two of them.
+    pub fn good_gaps(&self, boss: Boss) -> Option<(u32, u32)> {
+        let gaps = &self.gaps[boss as usize];
+        if gaps.count == 0 {
+            return None;
+        }
+        Some((gaps.total * 10 / gaps.count, gaps.max))
+    }
+}
+
+impl core::fmt::Display for Stats {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        writeln!(f, "Instabilities over {} days", self.days)?;
+        for fractal in Fractal::all() {
+            let counts = &self.instabs[fractal as usize];
+            if counts.iter().all(|&c| c == 0) {
+                continue;
+            }
+
+            writeln!(f, "{}", fractal)?;
+            // most common first
+            let mut order = [0u8; INSTAB_COUNT as usize];
+            for (i, o) in order.iter_mut().enumerate() {
+                *o = i as u8;
+            }
+            order.sort_unstable_by_key(|&i| (core::cmp::Reverse(counts[i as usize]), i));
+            for i in order.iter().filter(|&&i| counts[i as usize] > 0) {
+                let instab = Instability::from_u8(i + 1).unwrap();
+                writeln!(f, "  {:<20}{:>4}", instab, counts[*i as usize])?;
+            }
+        }
+
+        writeln!(f)?;
+        write!(f, "{:<12}", "Ratings")?;
+        for rating in (0..RATING_COUNT as u8).rev().filter_map(Rating::from_score) {
+            write!(f, "{:>12}", rating)?;
+        }
+        writeln!(f)?;
+        for boss in &Boss::ALL {
+            write!(f, "{:<12}", boss)?;
+            for rating in (0..RATING_COUNT as u8).rev().filter_map(Rating::from_score) {
+                write!(f, "{:>12}", self.rating_count(*boss, rating))?;
+            }
+            writeln!(f)?;
+        }
+
+        writeln!(f)?;
+        writeln!(f, "{:<12}{:>12}{:>12}", "Good gaps", "Average", "Max")?;
+        for boss in &Boss::ALL {
+            write!(f, "{:<12}", boss)?;
+            match self.good_gaps(*boss) {
+                Some((avg, max)) => writeln!(f, "{:>10}.{}{:>12}", avg / 10, avg % 10, max)?,
+                None => writeln!(f, "{:>12}{:>12}", "-", "-")?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::date::dataset_days;
+
+    #[test]
+    fn whole_dataset() {
+        let stats = Stats::collect(dataset_days());
+        assert_eq!(stats.days(), 366);
+
+        for boss in &Boss::ALL {
+            let total: u32 = (0..RATING_COUNT as u8)
+                .filter_map(Rating::from_score)
+                .map(|r| stats.rating_count(*boss, r))
+                .sum();
+            assert_eq!(total, 366);
+        }
+
+        for cm in &[
+            Fractal::Nightmare,
+            Fractal::ShatteredObservatory,
+            Fractal::SunquaPeak,
+        ] {
+            let total: u32 = (1..=INSTAB_COUNT)
+                .filter_map(Instability::from_u8)
+                .map(|i| stats.instab_count(*cm, i))
+                .sum();
+            assert_eq!(total, 366 * 3);
+        }
+    }
+
+    #[test]
+    fn gaps() {
+        let mut gaps = Gaps::default();
+        for idx in &[2, 3, 10, 14] {
+            gaps.push(*idx);
+        }
+        assert_eq!((gaps.count, gaps.total, gaps.max), (3, 12, 7));
+
+        // longer than u16 days can count
+        gaps.push(70_014);
+        assert_eq!((gaps.count, gaps.total, gaps.max), (4, 70_012, 70_000));
+    }
+}
@@ -0,0 +1,380 @@
+use crate::{instab::INSTAB_COUNT, Instability, Rater, Rating, Role};
+
+const MAX_MEMBERS: usize = 10;
+const MAX_NAME: usize = 16;
+
+/// How the members' individual ratings are folded into one.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Policy {
+    Worst,
+    Majority,
+    Average,
+}
+
+impl Policy {
+    /// What `from_str` accepts.
+    pub const KEYWORDS: [&'static str; 3] = ["worst", "majority", "average"];
+}
+
+impl core::str::FromStr for Policy {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use Policy::*;
+
+        Ok(match s {
+            "worst" => Worst,
+            "majority" => Majority,
+            "average" => Average,
+            _ => return Err(()),
+        })
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TeamError {
+    UnknownRole,
+    /// A word after the role that isn't `instability=rating`.
+    TrailingInput,
+    UnknownInstability,
+    UnknownRating,
+    NameTooLong,
+    NotText,
+    TooManyMembers,
+    Empty,
+}
+
+impl core::fmt::Display for TeamError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        use TeamError::*;
+
+        match self {
+            UnknownRole => write!(f, "unknown role"),
+            TrailingInput => write!(f, "expected instability=rating after the role"),
+            UnknownInstability => write!(f, "unknown instability in a profile"),
+            UnknownRating => write!(f, "unknown rating in a profile"),
+            NameTooLong => write!(f, "name longer than {} bytes", MAX_NAME),
+            NotText => write!(f, "not UTF-8 text"),
+            TooManyMembers => write!(f, "more than {} members", MAX_MEMBERS),
+            Empty => write!(f, "no members"),
+        }
+    }
+}
+
+/// A player: a name, maybe a role, and their own ratings of some
+/// instabilities.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Member {
+    name: [u8; MAX_NAME],
+    name_len: usize,
+    role: Option<Role>,
+    /// Per instability, what the member rates it regardless of role.
+    profile: [Option<Rating>; INSTAB_COUNT as usize],
+}
+
+impl Member {
+    pub fn new(name: &str, role: Option<Role>) -> Result<Self, TeamError> {
+        if name.len() > MAX_NAME {
+            return Err(TeamError::NameTooLong);
+        }
+        let mut member = Self {
+            name: [0; MAX_NAME],
+            name_len: name.len(),
+            role,
+            profile: [None; INSTAB_COUNT as usize],
+        };
+        member.name[..name.len()].copy_from_slice(name.as_bytes());
+        Ok(member)
+    }
+
+    pub fn name(&self) -> &str {
+        // copied from a `&str` whole
+        core::str::from_utf8(&self.name[..self.name_len]).unwrap_or_default()
+    }
+
+    pub const fn role(&self) -> Option<Role> {
+        self.role
+    }
+
+    /// Makes the member rate `instab` as `rating`, whatever `rater` says.
+    pub fn set(&mut self, instab: Instability, rating: Rating) {
+        self.profile[instab as usize] = Some(rating);
+    }
+
+    /// How the member sees `instab`: their own profile first, then their
+    /// role, then `rater`.
+    pub fn rate_one(&self, instab: &Instability, rater: &impl Rater) -> Rating {
+        self.profile[*instab as usize]
+            .or_else(|| self.role.and_then(|role| role.rate_one(instab)))
+            .unwrap_or_else(|| rater.rate_one(instab))
+    }
+}
+
+/// A group of players, each rating from their own point of view.
+#[derive(Debug, Copy, Clone)]
+pub struct Team {
+    members: [Option<Member>; MAX_MEMBERS],
+    len: usize,
+}
+
+impl Team {
+    pub const fn new() -> Self {
+        Self {
+            members: [None; MAX_MEMBERS],
+            len: 0,
+        }
+    }
+
+    pub fn push(&mut self, member: Member) -> Result<(), TeamError> {
+        if self.len == MAX_MEMBERS {
+            return Err(TeamError::TooManyMembers);
+        }
+        self.members[self.len] = Some(member);
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Parses a team file with one `name [role] [instability=rating...]`
+    /// member per line, like `ky healer flux-bomb=bad`. Blank lines and
+    /// lines starting with `#` are skipped.
+    pub fn parse(data: &[u8]) -> Result<Self, TeamError> {
+        use core::str::FromStr;
+
+        let data = core::str::from_utf8(data).map_err(|_| TeamError::NotText)?;
+        let mut team = Self::new();
+        for line in data.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut words = line.split_ascii_whitespace().peekable();
+            let name = words.next().unwrap_or_default();
+            let role = match words.next_if(|w| !w.contains('=')) {
+                Some(role) => Some(Role::from_str(role).map_err(|_| TeamError::UnknownRole)?),
+                None => None,
+            };
+            let mut member = Member::new(name, role)?;
+            for word in words {
+                let (instab, rating) = word.split_once('=').ok_or(TeamError::TrailingInput)?;
+                let instab =
+                    Instability::from_str(instab).map_err(|_| TeamError::UnknownInstability)?;
+                let rating = Rating::from_str(rating).map_err(|_| TeamError::UnknownRating)?;
+                member.set(instab, rating);
+            }
+            team.push(member)?;
+        }
+
+        if team.len == 0 {
+            return Err(TeamError::Empty);
+        }
+        Ok(team)
+    }
+
+    pub fn members(&self) -> impl Iterator<Item = &Member> {
+        self.members[..self.len].iter().flatten()
+    }
+}
+
+impl Default for Team {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Rates for a whole team by combining each member's view of `rater`.
+#[derive(Debug, Copy, Clone)]
+pub struct Consensus<T> {
+    rater: T,
+    team: Team,
+    policy: Policy,
+}
+
+impl<T> Consensus<T>
+where
+    T: Rater,
+{
+    pub const fn new(rater: T, team: Team, policy: Policy) -> Self {
+        Self {
+            rater,
+            team,
+            policy,
+        }
+    }
+}
+
+impl Policy {
+    /// Folds the members' scores, all on the same scale, into one.
+    fn combine(&self, scores: &mut [u8]) -> u8 {
+        match self {
+            Policy::Worst => scores.iter().copied().min().unwrap_or(0),
+            Policy::Majority => {
+                // highest score that more than half of the team agrees to
+                scores.sort_unstable_by(|a, b| b.cmp(a));
+                scores[scores.len() / 2]
+            }
+            Policy::Average => {
+                let len = scores.len() as u16;
+                let total: u16 = scores.iter().map(|&s| s as u16).sum();
+                ((total + len / 2) / len) as u8
+            }
+        }
+    }
+}
+
+impl<T> Consensus<T>
+where
+    T: Rater,
+{
+    /// Each member's `score` of what they'd rate, combined by the policy.
+    fn combine(&self, score: impl Fn(&Member) -> u8) -> u8 {
+        let mut scores = [0u8; MAX_MEMBERS];
+        for (slot, member) in scores.iter_mut().zip(self.team.members()) {
+            *slot = score(member);
+        }
+        self.policy.combine(&mut scores[..self.team.len])
+    }
+}
+
+/// One member's view on top of the team's rater.
+struct MemberRater<'a, T> {
+    member: &'a Member,
+    rater: &'a T,
+}
+
+impl<T> Rater for MemberRater<'_, T>
+where
+    T: Rater,
+{
+    fn rate_one(&self, instab: &Instability) -> Rating {
+        self.member.rate_one(instab, self.rater)
+    }
+}
+
+impl<T> Rater for Consensus<T>
+where
+    T: Rater,
+{
+    /// Used where single instabilities are shown, like their colours.
+    /// Dailies are rated as a whole by each member instead.
+    fn rate_one(&self, instab: &Instability) -> Rating {
+        let score = self.combine(|member| member.rate_one(instab, &self.rater).score());
+        Rating::from_score(score).unwrap()
+    }
+
+    fn rate_instabs<'a>(&self, instabs: impl Iterator<Item = &'a Instability> + Clone) -> Rating {
+        let score = self.combine(|member| {
+            let rater = MemberRater {
+                member,
+                rater: &self.rater,
+            };
+            rater.rate_instabs(instabs.clone()).score()
+        });
+        Rating::from_score(score).unwrap()
+    }
+
+    fn score_instabs<'a>(&self, instabs: impl Iterator<Item = &'a Instability> + Clone) -> u8 {
+        self.combine(|member| {
+            let rater = MemberRater {
+                member,
+                rater: &self.rater,
+            };
+            rater.score_instabs(instabs.clone())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Boss;
+
+    #[test]
+    fn parse_team_file() {
+        let team = Team::parse(
+            b"# our static\nky healer\n\nal kiter hamstrung=bad\n  sam flux-bomb=playable\n",
+        )
+        .unwrap();
+        let mut members = team.members();
+        let ky = members.next().unwrap();
+        assert_eq!((ky.name(), ky.role()), ("ky", Some(Role::Healer)));
+        let al = members.next().unwrap();
+        assert_eq!((al.name(), al.role()), ("al", Some(Role::Kiter)));
+        assert_eq!(
+            al.rate_one(&Instability::Hamstrung, &Boss::Arkk),
+            Rating::Bad
+        );
+        let sam = members.next().unwrap();
+        assert_eq!((sam.name(), sam.role()), ("sam", None));
+        assert_eq!(
+            sam.rate_one(&Instability::FluxBomb, &Boss::Arkk),
+            Rating::Playable
+        );
+        assert!(members.next().is_none());
+
+        assert_eq!(
+            Team::parse(b"ky tank\n").unwrap_err(),
+            TeamError::UnknownRole
+        );
+        assert_eq!(Team::parse(b"# nobody\n").unwrap_err(), TeamError::Empty);
+        assert_eq!(
+            Team::parse(b"ky healer dps\n").unwrap_err(),
+            TeamError::TrailingInput
+        );
+        assert_eq!(
+            Team::parse(b"ky flux-bomb=meh\n").unwrap_err(),
+            TeamError::UnknownRating
+        );
+    }
+
+    #[test]
+    fn consensus_policies() {
+        use crate::Boss::Arkk;
+        use Instability::*;
+        use Rating::*;
+
+        let team = Team::parse(b"a kiter\nb healer\nc\nd boon\ne dps\n").unwrap();
+        let rate = |policy| Consensus::new(Arkk, team, policy).rate_one(&Hamstrung);
+
+        // kiter: unplayable, everyone else: bad
+        assert_eq!(rate(Policy::Worst), Unplayable);
+        assert_eq!(rate(Policy::Majority), Bad);
+        assert_eq!(rate(Policy::Average), Bad);
+
+        let rate = |policy| Consensus::new(Arkk, team, policy).rate_one(&SocialAwkwardness);
+
+        // kiter: playable, dps: bad, everyone else: unplayable
+        assert_eq!(rate(Policy::Worst), Unplayable);
+        assert_eq!(rate(Policy::Majority), Unplayable);
+        assert_eq!(rate(Policy::Average), Bad);
+    }
+
+    #[test]
+    fn consensus_over_whole_dailies() {
+        use crate::{set::Instabs, Boss::Arkk, Rateable};
+        use Instability::*;
+        use Rating::*;
+
+        // arkk alone rates all three good, so the daily is good
+        let instabs = Instabs::new(Afflicted, Frailty, Outflanked);
+        let team =
+            Team::parse(b"a afflicted=unplayable\nb frailty=unplayable\nc outflanked=unplayable\n")
+                .unwrap();
+        let rate = |policy| instabs.rate(&Consensus::new(Arkk, team, policy));
+
+        // each instability on its own has a good majority, but every member
+        // has one they can't play, so the daily as a whole is unplayable
+        assert_eq!(
+            Consensus::new(Arkk, team, Policy::Majority).rate_one(&Afflicted),
+            Good
+        );
+        assert_eq!(rate(Policy::Majority), Unplayable);
+        assert_eq!(rate(Policy::Average), Unplayable);
+        assert_eq!(rate(Policy::Worst), Unplayable);
+
+        let team = Team::parse(b"a afflicted=unplayable\nb\nc\n").unwrap();
+        let rate = |policy| instabs.rate(&Consensus::new(Arkk, team, policy));
+        assert_eq!(rate(Policy::Worst), Unplayable);
+        assert_eq!(rate(Policy::Majority), Good);
+        assert_eq!(rate(Policy::Average), Playable);
+    }
+}
@@ -0,0 +1,11 @@
+/// Writes into a fixed buffer, counting the bytes written so far.
+pub(crate) struct Plain<'a>(pub &'a mut [u8], pub usize);
+
+impl core::fmt::Write for Plain<'_> {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        let end = self.1 + s.len();
+        self.0[self.1..end].copy_from_slice(s.as_bytes());
+        self.1 = end;
+        Ok(())
+    }
+}